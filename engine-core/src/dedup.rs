@@ -0,0 +1,164 @@
+use std::collections::{BTreeMap, HashMap};
+
+use protocol::{OrderId, OrderType, Price, Quantity, Side, UserId};
+
+/// The order parameters a resubmission under the same `client_order_id` is
+/// compared against, so an honest retry (identical fields) can be told apart
+/// from the id being reused for a different order entirely.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderFingerprint {
+    pub symbol: String,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub quantity: Quantity,
+    pub price: Option<Price>,
+}
+
+struct DedupEntry {
+    order_id: OrderId,
+    fingerprint: OrderFingerprint,
+    inserted_at_ms: i64,
+}
+
+/// A single user's `client_order_id` -> server `order_id` mappings, along
+/// with an index by insertion time so the oldest entries can be evicted
+/// once the user's bound is reached.
+#[derive(Default)]
+struct UserDedupState {
+    entries: HashMap<String, DedupEntry>,
+    by_inserted_at: BTreeMap<i64, Vec<String>>,
+}
+
+/// Result of checking whether a `client_order_id` has already been used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupResult {
+    /// Not seen before (or its entry expired); the caller should place the
+    /// order and the id is now tracked.
+    New,
+    /// Already seen for this user with matching order parameters; the caller
+    /// should return the original order's ack instead of placing a second
+    /// order.
+    Duplicate(OrderId),
+    /// Already seen for this user, but with different order parameters: the
+    /// id was reused rather than honestly retried. The caller should reject
+    /// with `RejectReason::DuplicateClientOrderId` instead of placing or
+    /// acking anything.
+    Conflict,
+}
+
+/// Bounded per-user map from `client_order_id` to the `order_id` it was
+/// assigned, so a client can safely retry a submission without risking a
+/// duplicate order. Entries are evicted on a combined LRU/TTL basis: the
+/// oldest entry is dropped once a user exceeds `capacity_per_user`, and any
+/// entry older than `ttl_ms` is treated as if it were never seen.
+pub struct ClientOrderIdRegistry {
+    users: HashMap<UserId, UserDedupState>,
+    capacity_per_user: usize,
+    ttl_ms: i64,
+}
+
+impl ClientOrderIdRegistry {
+    pub fn new(capacity_per_user: usize, ttl_ms: i64) -> Self {
+        Self {
+            users: HashMap::new(),
+            capacity_per_user,
+            ttl_ms,
+        }
+    }
+
+    /// Checks whether `client_order_id` was already submitted by `user_id`.
+    /// If it was (and hasn't expired), returns `Duplicate`/`Conflict`
+    /// depending on whether `fingerprint` matches what was recorded, without
+    /// recording anything new. Otherwise records `order_id`/`fingerprint`
+    /// against it and returns `New`, evicting the oldest entry first if the
+    /// user is already at capacity.
+    pub fn check_and_insert(
+        &mut self,
+        user_id: UserId,
+        client_order_id: &str,
+        order_id: OrderId,
+        fingerprint: OrderFingerprint,
+        now_ms: i64,
+    ) -> DedupResult {
+        let state = self.users.entry(user_id).or_default();
+
+        if let Some(entry) = state.entries.get(client_order_id) {
+            if now_ms - entry.inserted_at_ms <= self.ttl_ms {
+                return if entry.fingerprint == fingerprint {
+                    DedupResult::Duplicate(entry.order_id)
+                } else {
+                    DedupResult::Conflict
+                };
+            }
+            Self::remove_entry(state, client_order_id);
+        }
+
+        Self::evict_expired(state, now_ms, self.ttl_ms);
+
+        if state.entries.len() >= self.capacity_per_user {
+            if let Some((&oldest_ts, ids)) = state.by_inserted_at.iter_mut().next() {
+                if let Some(oldest_id) = ids.first().cloned() {
+                    ids.retain(|id| id != &oldest_id);
+                    if ids.is_empty() {
+                        state.by_inserted_at.remove(&oldest_ts);
+                    }
+                    state.entries.remove(&oldest_id);
+                }
+            }
+        }
+
+        state.entries.insert(
+            client_order_id.to_string(),
+            DedupEntry {
+                order_id,
+                fingerprint,
+                inserted_at_ms: now_ms,
+            },
+        );
+        state
+            .by_inserted_at
+            .entry(now_ms)
+            .or_default()
+            .push(client_order_id.to_string());
+
+        DedupResult::New
+    }
+
+    /// Resolves `client_order_id` back to the `order_id` it was assigned, for
+    /// a cancel that addresses the order by client id instead of server id.
+    /// `None` if never seen (or expired) for this user.
+    pub fn resolve(&self, user_id: UserId, client_order_id: &str, now_ms: i64) -> Option<OrderId> {
+        let entry = self.users.get(&user_id)?.entries.get(client_order_id)?;
+        if now_ms - entry.inserted_at_ms > self.ttl_ms {
+            return None;
+        }
+        Some(entry.order_id)
+    }
+
+    fn remove_entry(state: &mut UserDedupState, client_order_id: &str) {
+        if let Some(entry) = state.entries.remove(client_order_id) {
+            if let Some(ids) = state.by_inserted_at.get_mut(&entry.inserted_at_ms) {
+                ids.retain(|id| id != client_order_id);
+                if ids.is_empty() {
+                    state.by_inserted_at.remove(&entry.inserted_at_ms);
+                }
+            }
+        }
+    }
+
+    fn evict_expired(state: &mut UserDedupState, now_ms: i64, ttl_ms: i64) {
+        let expired_timestamps: Vec<i64> = state
+            .by_inserted_at
+            .range(..now_ms - ttl_ms)
+            .map(|(&ts, _)| ts)
+            .collect();
+
+        for ts in expired_timestamps {
+            if let Some(ids) = state.by_inserted_at.remove(&ts) {
+                for id in ids {
+                    state.entries.remove(&id);
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,112 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use chrono::Utc;
 use crossbeam_channel::{Receiver, Sender};
 use protocol::{
-    CancelOrder, CancelReason, Event, Order, OrderAck, OrderCancelled, OrderCommand, OrderReject,
-    RejectReason,
+    BulkCancelSummary, CancelOrder, CancelReason, Event, Fill, FillRole, Order, OrderAck,
+    OrderCancelled, OrderCommand, OrderId, OrderReject, OrderStatus, OrderTriggered, OrderType,
+    Price, Quantity, RejectReason, SelfTradeBehavior, Side, Trade, UserId,
 };
 
+use crate::dedup::{ClientOrderIdRegistry, DedupResult, OrderFingerprint};
+
+/// Per-user bound on how many `client_order_id`s the dedup registry
+/// remembers at once, and how long an entry stays eligible to be matched
+/// against a retry before it's treated as never seen.
+const CLIENT_ORDER_ID_CAPACITY_PER_USER: usize = 1024;
+const CLIENT_ORDER_ID_TTL_MS: i64 = 10 * 60 * 1000;
+
+/// A conditional (`Stop`/`StopLimit`/`TrailingStop`/`TrailingStopPct`) order
+/// dormant until the market trades through its trigger, held outside the
+/// live book so it never participates in matching as-is.
+struct PendingTrigger {
+    order: Order,
+    /// Running extreme price since the order was placed — the lowest trade
+    /// price seen for a buy-side trail, the highest for a sell-side trail —
+    /// used to recompute a trailing stop's effective trigger on every trade.
+    /// `None` until the first trade after the order was placed; unused by
+    /// `Stop`/`StopLimit`, whose trigger is fixed.
+    extreme: Option<Price>,
+}
+
+/// Cumulative fill bookkeeping for one order's lifetime, keyed by
+/// `order_id`, used to compute each `Fill` event's `cumulative_quantity`,
+/// volume-weighted `avg_fill_price`, and resulting `OrderStatus`. Inserted
+/// when the order is accepted and evicted once it reaches a terminal state
+/// (fully filled or cancelled).
+struct FillStats {
+    original_quantity: Quantity,
+    cumulative_quantity: Quantity,
+    cumulative_value: u128,
+}
+
+impl FillStats {
+    fn new(original_quantity: Quantity) -> Self {
+        Self {
+            original_quantity,
+            cumulative_quantity: 0,
+            cumulative_value: 0,
+        }
+    }
+
+    /// Folds one more trade into this order's running totals, returning the
+    /// cumulative quantity filled so far, the volume-weighted average fill
+    /// price, and whether the order is now fully filled.
+    fn record(&mut self, trade_qty: Quantity, trade_price: Price) -> (Quantity, Price, bool) {
+        self.cumulative_quantity += trade_qty;
+        self.cumulative_value += trade_price as u128 * trade_qty as u128;
+        let avg_price = (self.cumulative_value / self.cumulative_quantity as u128) as Price;
+        let filled = self.cumulative_quantity >= self.original_quantity;
+        (self.cumulative_quantity, avg_price, filled)
+    }
+}
+
+/// One symbol's resting orders, both sides. `Engine` keeps one of these per
+/// traded symbol so that a price level on one market never crosses against
+/// a same-priced level on another.
+#[derive(Default)]
+struct Book {
+    /// Resting buy orders, keyed by price ascending; the best bid is
+    /// `bids.keys().next_back()`.
+    bids: BTreeMap<Price, VecDeque<Order>>,
+    /// Resting sell orders, keyed by price ascending; the best ask is
+    /// `asks.keys().next()`.
+    asks: BTreeMap<Price, VecDeque<Order>>,
+}
+
+/// A symbol's maker/taker fee rates, in basis points (1/100 of a percent)
+/// of traded notional. `taker_bps` is ordinarily positive; `maker_bps` may
+/// be negative to pay the maker a rebate for providing liquidity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSchedule {
+    pub maker_bps: i32,
+    pub taker_bps: i32,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self {
+            maker_bps: 0,
+            taker_bps: 0,
+        }
+    }
+}
+
+impl FeeSchedule {
+    /// Computes `(maker_fee, taker_fee)` on a trade of `quantity` at `price`,
+    /// in the same integer quote-currency units as `Price`. Each fee is
+    /// floored (rounded toward negative infinity), then a negative maker
+    /// rebate is capped so the two fees never net negative overall.
+    fn compute_fees(&self, quantity: Quantity, price: Price) -> (i64, i64) {
+        let notional = quantity as i128 * price as i128;
+        let floor_bps = |bps: i32| (notional * bps as i128).div_euclid(10_000) as i64;
+
+        let taker_fee = floor_bps(self.taker_bps);
+        let maker_fee = floor_bps(self.maker_bps).max(-taker_fee);
+        (maker_fee, taker_fee)
+    }
+}
+
 /// synchronous matching engine
 /// runs in a dedicated thread, no async, deteministic, locks free
 ///
@@ -14,18 +117,62 @@ use protocol::{
 /// - Handling errors
 /// - Logging
 /// - Metrics
-
 pub struct Engine {
-    // TODO: Add orderbook state and active_orders here later
+    /// One resting book per traded symbol. `Engine` serves every market from
+    /// a single instance (see `fee_schedules` below), so the book itself must
+    /// be keyed by symbol too, or two different markets trading at the same
+    /// numeric price would cross against each other.
+    books: HashMap<String, Book>,
+    /// `order_id` -> `(symbol, side, price)` for every resting order, so a
+    /// cancel can find its book and price level in O(1) instead of scanning
+    /// every symbol's book.
+    index: HashMap<OrderId, (String, Side, Price)>,
+    /// Conditional orders waiting on a trigger, checked against `last_price`
+    /// after every trade instead of resting in a symbol's `Book`.
+    pending_triggers: Vec<PendingTrigger>,
+    /// The most recent trade price, used to arm pending conditional orders.
+    last_price: Option<Price>,
+    /// Cumulative fill bookkeeping per live `order_id`; see `FillStats`.
+    fill_stats: HashMap<OrderId, FillStats>,
+    /// Fee schedule applied to symbols with no entry in `fee_schedules`.
+    default_fee_schedule: FeeSchedule,
+    /// Per-symbol fee schedule overrides.
+    fee_schedules: HashMap<String, FeeSchedule>,
+    next_trade_id: u64,
+    /// Per-user `client_order_id` -> `order_id` dedup map, so a client can
+    /// safely retry `PlaceOrder`/`CancelOrder` without risking a duplicate
+    /// order or needing to remember the server-assigned id.
+    client_order_ids: ClientOrderIdRegistry,
 }
 
 impl Engine {
-    pub fn new() -> Self {
+    pub fn new(
+        default_fee_schedule: FeeSchedule,
+        fee_schedules: HashMap<String, FeeSchedule>,
+    ) -> Self {
         Self {
-            // TODO: Initialize orderbook
+            books: HashMap::new(),
+            index: HashMap::new(),
+            pending_triggers: Vec::new(),
+            last_price: None,
+            fill_stats: HashMap::new(),
+            default_fee_schedule,
+            fee_schedules,
+            next_trade_id: 1,
+            client_order_ids: ClientOrderIdRegistry::new(
+                CLIENT_ORDER_ID_CAPACITY_PER_USER,
+                CLIENT_ORDER_ID_TTL_MS,
+            ),
         }
     }
 
+    fn fee_schedule_for(&self, symbol: &str) -> FeeSchedule {
+        self.fee_schedules
+            .get(symbol)
+            .copied()
+            .unwrap_or(self.default_fee_schedule)
+    }
+
     pub fn run(&mut self, order_rx: Receiver<OrderCommand>, event_tx: Sender<Event>) {
         println!("[Engine] Starting matching engine...");
 
@@ -39,6 +186,15 @@ impl Engine {
                     println!("[Engine] Cancelling order: {cancel_order:?}");
                     self.handle_cancel_order(cancel_order, &event_tx);
                 }
+                Ok(OrderCommand::ExpireOrders(now_ts)) => {
+                    self.expire_orders(now_ts, &event_tx);
+                }
+                Ok(OrderCommand::CancelAllForUser { user_id, symbol }) => {
+                    self.handle_cancel_all_for_user(user_id, symbol, &event_tx);
+                }
+                Ok(OrderCommand::CancelOrderIds { user_id, order_ids }) => {
+                    self.handle_cancel_order_ids(user_id, order_ids, &event_tx);
+                }
                 Err(e) => {
                     println!("[Engine] Error receiving order command: {e}");
                     break;
@@ -49,65 +205,928 @@ impl Engine {
         println!("[Engine] Engine shutting down");
     }
 
-    fn handle_place_order(&self, order: Order, event_tx: &Sender<Event>) {
+    fn handle_place_order(&mut self, order: Order, event_tx: &Sender<Event>) {
         println!(
             "[Engine] Processing order: {} from user {}",
             order.order_id, order.user_id
         );
 
         if order.quantity == 0 {
-            let reject = Event::OrderReject(OrderReject {
+            Self::reject(
+                &order,
+                RejectReason::InvalidQuantity,
+                "order quantity must be greater than zero",
+                event_tx,
+            );
+            return;
+        }
+
+        if let Some(client_order_id) = order.client_order_id.clone() {
+            let fingerprint = OrderFingerprint {
+                symbol: order.symbol.clone(),
+                side: order.side,
+                order_type: order.order_type,
+                quantity: order.quantity,
+                price: order.price,
+            };
+
+            match self.client_order_ids.check_and_insert(
+                order.user_id,
+                &client_order_id,
+                order.order_id,
+                fingerprint,
+                now_ts() as i64,
+            ) {
+                DedupResult::Duplicate(original_order_id) => {
+                    // An honest retry: don't place a second order, just
+                    // re-ack the one the client already has.
+                    Self::emit(
+                        event_tx,
+                        Event::OrderAck(OrderAck {
+                            order_id: original_order_id,
+                            user_id: order.user_id,
+                            symbol: order.symbol.clone(),
+                            order_type: order.order_type,
+                        }),
+                    );
+                    return;
+                }
+                DedupResult::Conflict => {
+                    Self::reject(
+                        &order,
+                        RejectReason::DuplicateClientOrderId,
+                        "client_order_id was already used with different order parameters",
+                        event_tx,
+                    );
+                    return;
+                }
+                DedupResult::New => {}
+            }
+        }
+
+        let is_conditional = matches!(
+            order.order_type,
+            OrderType::Stop { .. }
+                | OrderType::StopLimit { .. }
+                | OrderType::TrailingStop { .. }
+                | OrderType::TrailingStopPct { .. }
+        );
+
+        if !is_conditional && order.order_type != OrderType::Market && order.price.is_none() {
+            Self::reject(
+                &order,
+                RejectReason::InvalidPrice,
+                "a non-market order requires a price",
+                event_tx,
+            );
+            return;
+        }
+
+        if let Some(max_ts) = order.max_ts {
+            if max_ts <= now_ts() {
+                Self::reject(
+                    &order,
+                    RejectReason::Expired,
+                    "order's max_ts had already elapsed at submission time",
+                    event_tx,
+                );
+                return;
+            }
+        }
+
+        let conditional_params_valid = match order.order_type {
+            OrderType::Stop { trigger } => trigger > 0,
+            OrderType::StopLimit { trigger, limit } => trigger > 0 && limit > 0,
+            OrderType::TrailingStop { offset } => offset > 0,
+            OrderType::TrailingStopPct { pct_bps } => pct_bps > 0,
+            _ => true,
+        };
+        if !conditional_params_valid {
+            Self::reject(
+                &order,
+                RejectReason::InvalidPrice,
+                "a conditional order requires a nonzero trigger/offset",
+                event_tx,
+            );
+            return;
+        }
+
+        if order.order_type == OrderType::PostOnly && self.would_cross(&order) {
+            Self::reject(
+                &order,
+                RejectReason::WouldCross,
+                "a PostOnly order would have immediately crossed and taken liquidity",
+                event_tx,
+            );
+            return;
+        }
+
+        if order.order_type == OrderType::FillOrKill && !self.can_fill_fully(&order) {
+            Self::reject(
+                &order,
+                RejectReason::UnfillableFillOrKill,
+                "insufficient resting liquidity to fill the order in full",
+                event_tx,
+            );
+            return;
+        }
+
+        self.fill_stats
+            .insert(order.order_id, FillStats::new(order.quantity));
+
+        if is_conditional {
+            // A conditional order always just starts out dormant, so it
+            // always acks immediately; it never reaches `match_order`'s
+            // ack-vs-terminal-fill decision below.
+            Self::emit(
+                event_tx,
+                Event::OrderAck(OrderAck {
+                    order_id: order.order_id,
+                    user_id: order.user_id,
+                    symbol: order.symbol.clone(),
+                    order_type: order.order_type,
+                }),
+            );
+            // Rests in the pending-trigger list instead of the live book
+            // until `last_price` moves through its trigger condition.
+            self.pending_triggers.push(PendingTrigger {
+                order,
+                extreme: None,
+            });
+            return;
+        }
+
+        self.match_order(order, event_tx, true);
+    }
+
+    fn handle_cancel_order(&mut self, cancel_order: CancelOrder, event_tx: &Sender<Event>) {
+        let order_id = match cancel_order.order_id {
+            Some(order_id) => Some(order_id),
+            None => cancel_order.client_order_id.as_deref().and_then(|client_order_id| {
+                self.client_order_ids
+                    .resolve(cancel_order.user_id, client_order_id, now_ts() as i64)
+            }),
+        };
+
+        let Some(order_id) = order_id else {
+            println!(
+                "[Engine] Cancel ignored: no order found for user {} (order_id={:?}, client_order_id={:?})",
+                cancel_order.user_id, cancel_order.order_id, cancel_order.client_order_id
+            );
+            return;
+        };
+
+        println!(
+            "[Engine] Cancelling order: {} from user {}",
+            order_id, cancel_order.user_id
+        );
+
+        let Some((symbol, side, price)) = self.index.remove(&order_id) else {
+            println!(
+                "[Engine] Cancel ignored: order {} is not resting on the book",
+                order_id
+            );
+            return;
+        };
+
+        if let Some(book) = self.books.get_mut(&symbol) {
+            let levels = match side {
+                Side::Buy => &mut book.bids,
+                Side::Sell => &mut book.asks,
+            };
+
+            if let Some(level) = levels.get_mut(&price) {
+                level.retain(|resting| resting.order_id != order_id);
+                if level.is_empty() {
+                    levels.remove(&price);
+                }
+            }
+        }
+        self.fill_stats.remove(&order_id);
+
+        Self::emit(
+            event_tx,
+            Event::OrderCancelled(OrderCancelled {
+                order_id,
+                user_id: cancel_order.user_id,
+                symbol: cancel_order.symbol,
+                reason: CancelReason::UserRequested,
+            }),
+        );
+    }
+
+    /// Sums resting quantity on the opposite side of the book that `order`
+    /// could actually cross against, stopping at its limit price (or, for a
+    /// market order, at any price). Used to pre-check `FillOrKill` orders
+    /// before they ack, since a partial fill isn't allowed to rest.
+    ///
+    /// Walks makers in the same price-time order `match_order` would, since
+    /// `order.user_id`'s own resting quantity is reachable differently
+    /// depending on `order.self_trade_behavior`: `DecrementTake`/
+    /// `CancelProvide` skip over a same-user maker and keep matching past it,
+    /// so it's excluded here too but the walk continues; `AbortTransaction`
+    /// instead halts matching outright the moment it hits one (see the
+    /// `AbortTransaction` arm in `match_order`), so no liquidity beyond that
+    /// point is actually reachable and the walk must stop there as well, or
+    /// this pre-check would pass a FOK order that can only partially fill.
+    fn can_fill_fully(&self, order: &Order) -> bool {
+        let Some(book) = self.books.get(&order.symbol) else {
+            return false;
+        };
+
+        let limit = match order.order_type {
+            OrderType::Market => match order.side {
+                Side::Buy => Price::MAX,
+                Side::Sell => 1,
+            },
+            _ => order.price.expect("validated in handle_place_order"),
+        };
+
+        let mut available: Quantity = 0;
+        match order.side {
+            Side::Buy => {
+                for (&price, level) in book.asks.iter() {
+                    if price > limit {
+                        break;
+                    }
+                    for maker in level.iter() {
+                        if maker.user_id == order.user_id {
+                            if order.self_trade_behavior == SelfTradeBehavior::AbortTransaction {
+                                return available >= order.quantity;
+                            }
+                            continue;
+                        }
+                        available += maker.quantity;
+                        if available >= order.quantity {
+                            return true;
+                        }
+                    }
+                }
+            }
+            Side::Sell => {
+                for (&price, level) in book.bids.iter().rev() {
+                    if price < limit {
+                        break;
+                    }
+                    for maker in level.iter() {
+                        if maker.user_id == order.user_id {
+                            if order.self_trade_behavior == SelfTradeBehavior::AbortTransaction {
+                                return available >= order.quantity;
+                            }
+                            continue;
+                        }
+                        available += maker.quantity;
+                        if available >= order.quantity {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+
+        available >= order.quantity
+    }
+
+    /// Whether `order` (a `PostOnly` order) would immediately take liquidity
+    /// if it were matched as-is, i.e. whether the opposite side's best price
+    /// on its own symbol's book already crosses its limit.
+    fn would_cross(&self, order: &Order) -> bool {
+        let Some(book) = self.books.get(&order.symbol) else {
+            return false;
+        };
+        let price = order.price.expect("validated in handle_place_order");
+
+        match order.side {
+            Side::Buy => book.asks.keys().next().is_some_and(|&ask| ask <= price),
+            Side::Sell => book.bids.keys().next_back().is_some_and(|&bid| bid >= price),
+        }
+    }
+
+    /// Walks the opposite side of the book in price-time priority, crossing
+    /// `order` against resting makers while their level's price is still
+    /// within `order`'s limit (a market order's implicit limit crosses any
+    /// resting price). Any limit-order residual rests on `order`'s own side;
+    /// a market order's residual is dropped.
+    ///
+    /// `emit_ack` is `true` for an order entering the book for the first
+    /// time (via `handle_place_order`) and `false` for one just armed by
+    /// `activate_pending`, which already announced itself via
+    /// `OrderTriggered` instead. When `true`, the order's own `OrderAck` is
+    /// suppressed if it fully fills in this call — a single terminal `Fill`
+    /// with `status: Filled` says everything an `Ack` would have — and
+    /// otherwise emitted just before its `Fill`s so a client always sees the
+    /// ack before the fills it explains.
+    fn match_order(&mut self, mut order: Order, event_tx: &Sender<Event>, emit_ack: bool) {
+        let maker_side = match order.side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+
+        // Market orders carry an implicit limit that crosses any resting price.
+        let limit = match order.order_type {
+            OrderType::Market => match order.side {
+                Side::Buy => Price::MAX,
+                Side::Sell => 1,
+            },
+            _ => order.price.expect("validated in handle_place_order"),
+        };
+
+        // Computed up front, before `book` borrows `self.books` below: fixed
+        // for the whole order, and `fee_schedule_for` needs an unborrowed
+        // `&self`.
+        let fee_schedule = self.fee_schedule_for(&order.symbol);
+        let book = self.books.entry(order.symbol.clone()).or_default();
+
+        let mut filled_any = false;
+        // Prices of trades executed below, replayed through
+        // `on_trade_price` once this function's mutable borrow of `book` is
+        // out of scope, so pending conditional orders arm off the same
+        // sequence of prices a client observes.
+        let mut trade_prices: Vec<Price> = Vec::new();
+        // The taker's own `Fill`s, held back until this function decides
+        // whether `order` also gets an `OrderAck` (see `emit_ack` above),
+        // so the ack always precedes the fills it explains.
+        let mut taker_fills: Vec<Fill> = Vec::new();
+
+        loop {
+            if order.quantity == 0 {
+                break;
+            }
+
+            let best_price = match order.side {
+                Side::Buy => book.asks.keys().next().copied(),
+                Side::Sell => book.bids.keys().next_back().copied(),
+            };
+
+            let Some(maker_price) = best_price else {
+                break;
+            };
+
+            let crosses = match order.side {
+                Side::Buy => maker_price <= limit,
+                Side::Sell => maker_price >= limit,
+            };
+            if !crosses {
+                break;
+            }
+
+            let levels = match order.side {
+                Side::Buy => &mut book.asks,
+                Side::Sell => &mut book.bids,
+            };
+
+            let Some(level) = levels.get_mut(&maker_price) else {
+                break;
+            };
+
+            let Some(maker) = level.front_mut() else {
+                levels.remove(&maker_price);
+                continue;
+            };
+
+            if maker.user_id == order.user_id {
+                let maker_order_id = maker.order_id;
+                let maker_quantity = maker.quantity;
+
+                match order.self_trade_behavior {
+                    SelfTradeBehavior::CancelProvide => {
+                        level.pop_front();
+                        self.index.remove(&maker_order_id);
+                        self.fill_stats.remove(&maker_order_id);
+                        if level.is_empty() {
+                            levels.remove(&maker_price);
+                        }
+
+                        Self::emit(
+                            event_tx,
+                            Event::OrderCancelled(OrderCancelled {
+                                order_id: maker_order_id,
+                                user_id: order.user_id,
+                                symbol: order.symbol.clone(),
+                                reason: CancelReason::SelfTrade,
+                            }),
+                        );
+                        continue;
+                    }
+                    SelfTradeBehavior::DecrementTake => {
+                        // Neither side trades; the taker just gives up the
+                        // conflicting quantity and the maker rests untouched.
+                        order.quantity = order.quantity.saturating_sub(maker_quantity);
+                        continue;
+                    }
+                    SelfTradeBehavior::AbortTransaction => {
+                        if !filled_any {
+                            self.fill_stats.remove(&order.order_id);
+                            Self::reject(
+                                &order,
+                                RejectReason::SelfTradeNotAllowed,
+                                "order would self-trade against its own resting order",
+                                event_tx,
+                            );
+                            return;
+                        }
+                        // Fills against other makers already went out, so the
+                        // whole order can no longer be aborted; stop matching
+                        // here instead and let residual handling take over.
+                        break;
+                    }
+                }
+            }
+
+            let trade_qty = order.quantity.min(maker.quantity);
+            maker.quantity -= trade_qty;
+            order.quantity -= trade_qty;
+
+            let maker_order_id = maker.order_id;
+            let maker_user_id = maker.user_id;
+            let maker_remaining = maker.quantity;
+
+            if maker_remaining == 0 {
+                level.pop_front();
+                self.index.remove(&maker_order_id);
+            }
+            if level.is_empty() {
+                levels.remove(&maker_price);
+            }
+
+            filled_any = true;
+            trade_prices.push(maker_price);
+            let trade_id = self.next_trade_id;
+            self.next_trade_id += 1;
+
+            let (maker_fee, taker_fee) = fee_schedule.compute_fees(trade_qty, maker_price);
+
+            Self::emit(
+                event_tx,
+                Event::Trade(Trade {
+                    trade_id,
+                    maker_order_id,
+                    maker_user_id,
+                    taker_order_id: order.order_id,
+                    taker_user_id: order.user_id,
+                    symbol: order.symbol.clone(),
+                    quantity: trade_qty,
+                    price: maker_price,
+                    maker_fee,
+                    taker_fee,
+                    timestamp: Utc::now().timestamp_millis(),
+                }),
+            );
+
+            // The maker was already acked when it first rested, so its own
+            // `Fill` always emits immediately.
+            let maker_stats = self
+                .fill_stats
+                .get_mut(&maker_order_id)
+                .expect("inserted in handle_place_order");
+            let (maker_cumulative, maker_avg_price, maker_filled) =
+                maker_stats.record(trade_qty, maker_price);
+            if maker_filled {
+                self.fill_stats.remove(&maker_order_id);
+            }
+
+            Self::emit(
+                event_tx,
+                Event::Fill(Fill {
+                    trade_id,
+                    order_id: maker_order_id,
+                    user_id: maker_user_id,
+                    symbol: order.symbol.clone(),
+                    side: maker_side,
+                    role: FillRole::Maker,
+                    filled_quantity: trade_qty,
+                    filled_price: maker_price,
+                    remaining_quantity: maker_remaining,
+                    cumulative_quantity: maker_cumulative,
+                    avg_fill_price: maker_avg_price,
+                    status: if maker_filled {
+                        OrderStatus::Filled
+                    } else {
+                        OrderStatus::PartiallyFilled
+                    },
+                }),
+            );
+
+            let taker_stats = self
+                .fill_stats
+                .get_mut(&order.order_id)
+                .expect("inserted in handle_place_order");
+            let (taker_cumulative, taker_avg_price, taker_filled) =
+                taker_stats.record(trade_qty, maker_price);
+
+            taker_fills.push(Fill {
+                trade_id,
                 order_id: order.order_id,
                 user_id: order.user_id,
-                reason: RejectReason::InvalidQuantity,
+                symbol: order.symbol.clone(),
+                side: order.side,
+                role: FillRole::Taker,
+                filled_quantity: trade_qty,
+                filled_price: maker_price,
+                remaining_quantity: order.quantity,
+                cumulative_quantity: taker_cumulative,
+                avg_fill_price: taker_avg_price,
+                status: if taker_filled {
+                    OrderStatus::Filled
+                } else {
+                    OrderStatus::PartiallyFilled
+                },
             });
+        }
 
-            if let Err(e) = event_tx.send(reject) {
-                eprintln!("[Engine] Failed to send event: {}", e);
-            };
+        if order.quantity == 0 {
+            if !filled_any {
+                // The order's quantity reached zero purely by self-trade
+                // decrements (`DecrementTake`), never actually trading: there's
+                // no fill to imply an ack the way there is below, and with
+                // `taker_fills` empty this would otherwise emit nothing at
+                // all. Ack (if due) and report the same synthetic zero-fill
+                // terminal event as an untouched IOC/FOK/market order.
+                if emit_ack {
+                    Self::emit(
+                        event_tx,
+                        Event::OrderAck(OrderAck {
+                            order_id: order.order_id,
+                            user_id: order.user_id,
+                            symbol: order.symbol.clone(),
+                            order_type: order.order_type,
+                        }),
+                    );
+                }
+
+                let trade_id = self.next_trade_id;
+                self.next_trade_id += 1;
+
+                taker_fills.push(Fill {
+                    trade_id,
+                    order_id: order.order_id,
+                    user_id: order.user_id,
+                    symbol: order.symbol.clone(),
+                    side: order.side,
+                    role: FillRole::Taker,
+                    filled_quantity: 0,
+                    filled_price: 0,
+                    remaining_quantity: 0,
+                    cumulative_quantity: 0,
+                    avg_fill_price: 0,
+                    status: OrderStatus::Pending,
+                });
+            }
+
+            // Fully filled in this call: the last fill already says
+            // everything an `OrderAck` would have, so it's skipped entirely.
+            self.fill_stats.remove(&order.order_id);
+            for fill in taker_fills {
+                Self::emit(event_tx, Event::Fill(fill));
+            }
+            self.cascade_trigger_checks(trade_prices, event_tx);
             return;
         }
 
-        let ack = Event::OrderAck(OrderAck {
-            order_id: order.order_id,
-            user_id: order.user_id,
-            symbol: order.symbol,
-        });
+        if matches!(
+            order.order_type,
+            OrderType::Market | OrderType::ImmediateOrCancel | OrderType::FillOrKill
+        ) {
+            if emit_ack {
+                Self::emit(
+                    event_tx,
+                    Event::OrderAck(OrderAck {
+                        order_id: order.order_id,
+                        user_id: order.user_id,
+                        symbol: order.symbol.clone(),
+                        order_type: order.order_type,
+                    }),
+                );
+            }
+
+            // Already reported via the last fill's `remaining_quantity` if
+            // anything matched; an untouched order still needs a signal that
+            // its full quantity was dropped instead of resting.
+            if !filled_any {
+                let trade_id = self.next_trade_id;
+                self.next_trade_id += 1;
+
+                taker_fills.push(Fill {
+                    trade_id,
+                    order_id: order.order_id,
+                    user_id: order.user_id,
+                    symbol: order.symbol.clone(),
+                    side: order.side,
+                    role: FillRole::Taker,
+                    filled_quantity: 0,
+                    filled_price: 0,
+                    remaining_quantity: order.quantity,
+                    cumulative_quantity: 0,
+                    avg_fill_price: 0,
+                    status: OrderStatus::Pending,
+                });
+            }
+            for fill in taker_fills {
+                Self::emit(event_tx, Event::Fill(fill));
+            }
+            // Dropped for good (IOC/FOK residual, or an unfilled market
+            // order) rather than resting, so there's nothing left to track.
+            self.fill_stats.remove(&order.order_id);
+            self.cascade_trigger_checks(trade_prices, event_tx);
+            return;
+        }
+
+        if emit_ack {
+            Self::emit(
+                event_tx,
+                Event::OrderAck(OrderAck {
+                    order_id: order.order_id,
+                    user_id: order.user_id,
+                    symbol: order.symbol.clone(),
+                    order_type: order.order_type,
+                }),
+            );
+        }
+        for fill in taker_fills {
+            Self::emit(event_tx, Event::Fill(fill));
+        }
 
-        if let Err(e) = event_tx.send(ack) {
+        let price = order.price.expect("validated in handle_place_order");
+        self.index
+            .insert(order.order_id, (order.symbol.clone(), order.side, price));
+        match order.side {
+            Side::Buy => book.bids.entry(price).or_default().push_back(order),
+            Side::Sell => book.asks.entry(price).or_default().push_back(order),
+        };
+        self.cascade_trigger_checks(trade_prices, event_tx);
+    }
+
+    fn reject(
+        order: &Order,
+        reason: RejectReason,
+        message: &str,
+        event_tx: &Sender<Event>,
+    ) {
+        Self::emit(
+            event_tx,
+            Event::OrderReject(OrderReject {
+                order_id: order.order_id,
+                user_id: order.user_id,
+                reason,
+                message: message.to_string(),
+                symbol: order.symbol.clone(),
+            }),
+        );
+    }
+
+    fn emit(event_tx: &Sender<Event>, event: Event) {
+        if let Err(e) = event_tx.send(event) {
             eprintln!("[Engine] Failed to send event: {}", e);
         }
+    }
 
-        return;
+    /// Sweeps every resting order whose `max_ts` is at or before `now_ts` off
+    /// both sides of the book, cleaning up `self.index` and emitting
+    /// `OrderCancelled { reason: Expired }` for each one removed.
+    fn expire_orders(&mut self, now_ts: u64, event_tx: &Sender<Event>) {
+        self.cancel_matching(
+            |order| order.max_ts.is_some_and(|max_ts| max_ts <= now_ts),
+            CancelReason::Expired,
+            event_tx,
+        );
+    }
 
-        // TODO: match order in the orderbook later
+    /// Cancels every resting order owned by `user_id`, optionally restricted
+    /// to `symbol`, and reports how many were actually live via a
+    /// `BulkCancelSummary`.
+    fn handle_cancel_all_for_user(
+        &mut self,
+        user_id: UserId,
+        symbol: Option<String>,
+        event_tx: &Sender<Event>,
+    ) {
+        let cancelled_count = self.cancel_matching(
+            |order| {
+                order.user_id == user_id
+                    && symbol.as_deref().map_or(true, |s| order.symbol == s)
+            },
+            CancelReason::UserRequested,
+            event_tx,
+        );
+
+        Self::emit(
+            event_tx,
+            Event::BulkCancelSummary(BulkCancelSummary {
+                user_id,
+                symbol,
+                cancelled_count,
+            }),
+        );
     }
 
-    fn handle_cancel_order(&self, cancel_order: CancelOrder, event_tx: &Sender<Event>) {
-        println!(
-            "[Engine] Cancelling order: {} from user {}",
-            cancel_order.order_id, cancel_order.user_id
+    /// Cancels the subset of `order_ids` owned by `user_id` that are still
+    /// resting, and reports how many were actually live via a
+    /// `BulkCancelSummary`.
+    fn handle_cancel_order_ids(
+        &mut self,
+        user_id: UserId,
+        order_ids: Vec<OrderId>,
+        event_tx: &Sender<Event>,
+    ) {
+        let ids: std::collections::HashSet<OrderId> = order_ids.into_iter().collect();
+        let cancelled_count = self.cancel_matching(
+            |order| order.user_id == user_id && ids.contains(&order.order_id),
+            CancelReason::UserRequested,
+            event_tx,
+        );
+
+        Self::emit(
+            event_tx,
+            Event::BulkCancelSummary(BulkCancelSummary {
+                user_id,
+                symbol: None,
+                cancelled_count,
+            }),
         );
+    }
+
+    /// Removes every resting order matching `predicate` from both sides of
+    /// the book, cleaning up `self.index` and emitting `OrderCancelled` with
+    /// `reason` for each one removed. Returns the number removed.
+    fn cancel_matching(
+        &mut self,
+        predicate: impl Fn(&Order) -> bool,
+        reason: CancelReason,
+        event_tx: &Sender<Event>,
+    ) -> usize {
+        let mut index = std::mem::take(&mut self.index);
+        let mut count = 0;
+        for book in self.books.values_mut() {
+            count += Self::sweep_side(
+                &mut book.bids,
+                &mut index,
+                &mut self.fill_stats,
+                &predicate,
+                reason,
+                event_tx,
+            );
+            count += Self::sweep_side(
+                &mut book.asks,
+                &mut index,
+                &mut self.fill_stats,
+                &predicate,
+                reason,
+                event_tx,
+            );
+        }
+        self.index = index;
+        count
+    }
 
-        let cancelled = Event::OrderCancelled(OrderCancelled {
-            order_id: cancel_order.order_id,
-            user_id: cancel_order.user_id,
-            symbol: cancel_order.symbol,
-            reason: CancelReason::UserRequested,
+    fn sweep_side(
+        levels: &mut BTreeMap<Price, VecDeque<Order>>,
+        index: &mut HashMap<OrderId, (String, Side, Price)>,
+        fill_stats: &mut HashMap<OrderId, FillStats>,
+        predicate: impl Fn(&Order) -> bool,
+        reason: CancelReason,
+        event_tx: &Sender<Event>,
+    ) -> usize {
+        let mut count = 0;
+        levels.retain(|_, level| {
+            level.retain(|order| {
+                let matches = predicate(order);
+                if matches {
+                    count += 1;
+                    index.remove(&order.order_id);
+                    fill_stats.remove(&order.order_id);
+                    Self::emit(
+                        event_tx,
+                        Event::OrderCancelled(OrderCancelled {
+                            order_id: order.order_id,
+                            user_id: order.user_id,
+                            symbol: order.symbol.clone(),
+                            reason,
+                        }),
+                    );
+                }
+                !matches
+            });
+            !level.is_empty()
         });
+        count
+    }
 
-        if let Err(e) = event_tx.send(cancelled) {
-            eprint!("[Engine] Failed to send event: {}", e);
-        };
+    /// Replays each trade price from a just-completed `match_order` call
+    /// through `self.last_price` and the pending-trigger list, in order, so
+    /// a conditional order arms off the same price sequence a client would
+    /// observe.
+    fn cascade_trigger_checks(&mut self, trade_prices: Vec<Price>, event_tx: &Sender<Event>) {
+        for price in trade_prices {
+            self.on_trade_price(price, event_tx);
+        }
+    }
 
-        return;
+    /// Updates `last_price` and activates every pending conditional order
+    /// whose trigger condition `price` satisfies, one at a time, since
+    /// activating one can itself trade and move the price further.
+    fn on_trade_price(&mut self, price: Price, event_tx: &Sender<Event>) {
+        self.last_price = Some(price);
 
-        // TODO: cancel order in the orderbook later
+        loop {
+            let activated = self
+                .pending_triggers
+                .iter_mut()
+                .position(|pending| Self::check_and_update_trigger(pending, price));
+
+            let Some(idx) = activated else { break };
+            let pending = self.pending_triggers.remove(idx);
+            self.activate_pending(pending, price, event_tx);
+        }
+    }
+
+    /// Updates a pending order's running `extreme` (trailing types only)
+    /// and reports whether `price` now satisfies its trigger condition: a
+    /// buy stop triggers when `price >= trigger`, a sell stop when `price <=
+    /// trigger`.
+    fn check_and_update_trigger(pending: &mut PendingTrigger, price: Price) -> bool {
+        match pending.order.order_type {
+            OrderType::Stop { trigger } | OrderType::StopLimit { trigger, .. } => {
+                match pending.order.side {
+                    Side::Buy => price >= trigger,
+                    Side::Sell => price <= trigger,
+                }
+            }
+            OrderType::TrailingStop { offset } => Self::trailing_triggered(pending, price, offset),
+            OrderType::TrailingStopPct { pct_bps } => {
+                let base = pending.extreme.unwrap_or(price);
+                let offset = ((base as u128 * pct_bps as u128) / 10_000) as Price;
+                Self::trailing_triggered(pending, price, offset)
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves a trailing order's running extreme toward `price` (the lowest
+    /// price seen for a buy-trail, the highest for a sell-trail) and checks
+    /// whether `price` has reversed `offset` away from it.
+    fn trailing_triggered(pending: &mut PendingTrigger, price: Price, offset: Price) -> bool {
+        match pending.order.side {
+            Side::Buy => {
+                let extreme = pending.extreme.map_or(price, |e| e.min(price));
+                pending.extreme = Some(extreme);
+                price >= extreme.saturating_add(offset)
+            }
+            Side::Sell => {
+                let extreme = pending.extreme.map_or(price, |e| e.max(price));
+                pending.extreme = Some(extreme);
+                price <= extreme.saturating_sub(offset)
+            }
+        }
     }
+
+    /// Converts a triggered pending order into the live order its type arms
+    /// as — `Stop`/`TrailingStop`/`TrailingStopPct` become `Market`,
+    /// `StopLimit` becomes `Limit` at its configured limit price — emits
+    /// `OrderTriggered`, and runs it through the normal matching path.
+    fn activate_pending(
+        &mut self,
+        pending: PendingTrigger,
+        trigger_price: Price,
+        event_tx: &Sender<Event>,
+    ) {
+        let mut order = pending.order;
+
+        match order.order_type {
+            OrderType::Stop { .. }
+            | OrderType::TrailingStop { .. }
+            | OrderType::TrailingStopPct { .. } => {
+                order.order_type = OrderType::Market;
+                order.price = None;
+            }
+            OrderType::StopLimit { limit, .. } => {
+                order.order_type = OrderType::Limit;
+                order.price = Some(limit);
+            }
+            _ => unreachable!("only conditional order types are ever pending"),
+        }
+
+        Self::emit(
+            event_tx,
+            Event::OrderTriggered(OrderTriggered {
+                order_id: order.order_id,
+                user_id: order.user_id,
+                symbol: order.symbol.clone(),
+                trigger_price,
+            }),
+        );
+
+        self.match_order(order, event_tx, false);
+    }
+}
+
+/// Current unix timestamp in milliseconds, used to judge an order's `max_ts`
+/// against "now" both at submission time and during a periodic expiry sweep.
+fn now_ts() -> u64 {
+    Utc::now().timestamp_millis() as u64
 }
 
 impl Default for Engine {
     fn default() -> Self {
-        Self::new()
+        Self::new(FeeSchedule::default(), HashMap::new())
     }
 }
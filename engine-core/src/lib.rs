@@ -0,0 +1,7 @@
+pub mod dedup;
+pub mod engine;
+
+#[cfg(test)]
+mod tests;
+
+pub use dedup::{ClientOrderIdRegistry, DedupResult, OrderFingerprint};
@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use protocol::{
+    CancelOrder, Event, Fill, FillRole, Order, OrderCommand, OrderStatus, OrderType, RejectReason,
+    SelfTradeBehavior, Side, Trade,
+};
+
+use crate::engine::{Engine, FeeSchedule};
+
+const SYMBOL: &str = "SOL_USDC";
+
+/// Runs `engine` in a background thread fed by `commands`, then waits for it
+/// to settle and collects every `Event` it emitted, in order. Mirrors
+/// market-data's pipeline test harness: spawn on a channel, give it a moment
+/// to drain, drop the sender to stop the loop, then join.
+fn run_engine(mut engine: Engine, commands: Vec<OrderCommand>) -> Vec<Event> {
+    let (order_tx, order_rx) = crossbeam_channel::unbounded::<OrderCommand>();
+    let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
+
+    let handle = thread::spawn(move || {
+        engine.run(order_rx, event_tx);
+    });
+
+    for command in commands {
+        order_tx.send(command).unwrap();
+    }
+
+    thread::sleep(Duration::from_millis(50));
+    drop(order_tx);
+    handle.join().unwrap();
+
+    event_rx.try_iter().collect()
+}
+
+fn new_engine() -> Engine {
+    Engine::new(FeeSchedule::default(), HashMap::new())
+}
+
+fn limit_order(order_id: u64, user_id: u64, side: Side, quantity: u64, price: u64) -> Order {
+    Order::new(
+        order_id,
+        user_id,
+        SYMBOL.to_string(),
+        side,
+        OrderType::Limit,
+        quantity,
+        Some(price),
+    )
+}
+
+// ========== Self-Trade Behavior Tests ==========
+
+#[test]
+fn test_self_trade_decrement_take_shrinks_taker_without_trading() {
+    let engine = new_engine();
+    let maker = limit_order(1, 100, Side::Sell, 10, 50_000);
+    let mut taker = limit_order(2, 100, Side::Buy, 10, 50_000);
+    taker.self_trade_behavior = SelfTradeBehavior::DecrementTake;
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(maker),
+            OrderCommand::PlaceOrder(taker),
+            // The maker should be untouched by the decrement, so it must
+            // still be resting and cancellable afterwards.
+            OrderCommand::CancelOrder(CancelOrder::new(1, 100, SYMBOL.to_string())),
+        ],
+    );
+
+    // The taker absorbs the conflicting quantity against its own resting
+    // order with no `Trade` and no `Fill` actually exchanging anything.
+    assert!(
+        !events.iter().any(|e| matches!(e, Event::Trade(_))),
+        "DecrementTake must never generate a trade: {events:?}"
+    );
+
+    let taker_fill = events
+        .iter()
+        .find_map(|e| match e {
+            Event::Fill(fill) if fill.order_id == 2 => Some(fill),
+            _ => None,
+        })
+        .expect("taker order should still report a terminal zero-fill event");
+    assert_eq!(taker_fill.filled_quantity, 0);
+    assert_eq!(taker_fill.status, OrderStatus::Pending);
+
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, Event::OrderCancelled(c) if c.order_id == 1)),
+        "maker should still be resting and cancellable: {events:?}"
+    );
+}
+
+#[test]
+fn test_self_trade_cancel_provide_cancels_maker_and_continues_matching() {
+    let engine = new_engine();
+    let resting_other = limit_order(1, 200, Side::Sell, 5, 50_000);
+    let resting_same_user = limit_order(2, 100, Side::Sell, 5, 50_000);
+    let mut taker = limit_order(3, 100, Side::Buy, 10, 50_000);
+    taker.self_trade_behavior = SelfTradeBehavior::CancelProvide;
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(resting_same_user),
+            OrderCommand::PlaceOrder(resting_other),
+            OrderCommand::PlaceOrder(taker),
+        ],
+    );
+
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, Event::OrderCancelled(c) if c.order_id == 2)),
+        "same-user maker should have been cancelled: {events:?}"
+    );
+
+    let trades: Vec<&Trade> = events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Trade(trade) => Some(trade),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(trades.len(), 1, "taker should still match the other user's resting order");
+    assert_eq!(trades[0].maker_order_id, 1);
+    assert_eq!(trades[0].quantity, 5);
+}
+
+#[test]
+fn test_self_trade_abort_transaction_rejects_before_any_fill() {
+    let engine = new_engine();
+    let maker = limit_order(1, 100, Side::Sell, 10, 50_000);
+    let mut taker = limit_order(2, 100, Side::Buy, 10, 50_000);
+    taker.self_trade_behavior = SelfTradeBehavior::AbortTransaction;
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(maker),
+            OrderCommand::PlaceOrder(taker),
+        ],
+    );
+
+    assert!(
+        events.iter().any(
+            |e| matches!(e, Event::OrderReject(r) if r.order_id == 2 && r.reason == RejectReason::SelfTradeNotAllowed)
+        ),
+        "AbortTransaction should reject the taker outright: {events:?}"
+    );
+    assert!(!events.iter().any(|e| matches!(e, Event::Trade(_))));
+}
+
+// ========== Multi-Symbol Isolation Tests ==========
+
+#[test]
+fn test_resting_order_does_not_cross_a_different_symbol_at_the_same_price() {
+    let engine = new_engine();
+    let other_symbol = "BTC_USDC";
+    let resting_ask = Order::new(1, 200, other_symbol.to_string(), Side::Sell, OrderType::Limit, 10, Some(50_000));
+    let taker = limit_order(2, 100, Side::Buy, 10, 50_000);
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(resting_ask),
+            OrderCommand::PlaceOrder(taker),
+        ],
+    );
+
+    assert!(
+        !events.iter().any(|e| matches!(e, Event::Trade(_))),
+        "a resting order in another symbol's book must not cross: {events:?}"
+    );
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, Event::OrderAck(a) if a.order_id == 2)),
+        "taker should still rest with an ack: {events:?}"
+    );
+    assert!(
+        !events.iter().any(|e| matches!(e, Event::Fill(f) if f.order_id == 2)),
+        "a resting taker with nothing to cross must not report a fill: {events:?}"
+    );
+}
+
+// ========== PostOnly Tests ==========
+
+#[test]
+fn test_post_only_rejects_when_it_would_cross() {
+    let engine = new_engine();
+    let maker = limit_order(1, 200, Side::Sell, 10, 50_000);
+    let taker = Order::new(
+        2,
+        100,
+        SYMBOL.to_string(),
+        Side::Buy,
+        OrderType::PostOnly,
+        10,
+        Some(50_000),
+    );
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(maker),
+            OrderCommand::PlaceOrder(taker),
+        ],
+    );
+
+    assert!(events.iter().any(
+        |e| matches!(e, Event::OrderReject(r) if r.order_id == 2 && r.reason == RejectReason::WouldCross)
+    ));
+    assert!(!events.iter().any(|e| matches!(e, Event::Trade(_))));
+}
+
+#[test]
+fn test_post_only_rests_when_it_would_not_cross() {
+    let engine = new_engine();
+    let maker = limit_order(1, 200, Side::Sell, 10, 50_000);
+    let taker = Order::new(
+        2,
+        100,
+        SYMBOL.to_string(),
+        Side::Buy,
+        OrderType::PostOnly,
+        10,
+        Some(49_000),
+    );
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(maker),
+            OrderCommand::PlaceOrder(taker),
+            OrderCommand::CancelOrder(CancelOrder::new(2, 100, SYMBOL.to_string())),
+        ],
+    );
+
+    assert!(!events.iter().any(|e| matches!(e, Event::OrderReject(r) if r.order_id == 2)));
+    assert!(!events.iter().any(|e| matches!(e, Event::Trade(_))));
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, Event::OrderCancelled(c) if c.order_id == 2)),
+        "a non-crossing PostOnly order should rest and be cancellable: {events:?}"
+    );
+}
+
+// ========== FillOrKill Tests ==========
+
+#[test]
+fn test_fok_accepts_when_fully_fillable() {
+    let engine = new_engine();
+    let maker = limit_order(1, 200, Side::Sell, 10, 50_000);
+    let taker = Order::new(
+        2,
+        100,
+        SYMBOL.to_string(),
+        Side::Buy,
+        OrderType::FillOrKill,
+        10,
+        Some(50_000),
+    );
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(maker),
+            OrderCommand::PlaceOrder(taker),
+        ],
+    );
+
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, Event::OrderReject(r) if r.order_id == 2)));
+    let taker_fills: Vec<&Fill> = events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Fill(fill) if fill.order_id == 2 && fill.role == FillRole::Taker => Some(fill),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(taker_fills.len(), 1);
+    assert_eq!(taker_fills[0].filled_quantity, 10);
+    assert_eq!(taker_fills[0].status, OrderStatus::Filled);
+}
+
+#[test]
+fn test_fok_rejects_when_resting_liquidity_is_insufficient() {
+    let engine = new_engine();
+    let maker = limit_order(1, 200, Side::Sell, 5, 50_000);
+    let taker = Order::new(
+        2,
+        100,
+        SYMBOL.to_string(),
+        Side::Buy,
+        OrderType::FillOrKill,
+        10,
+        Some(50_000),
+    );
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(maker),
+            OrderCommand::PlaceOrder(taker),
+        ],
+    );
+
+    assert!(events.iter().any(
+        |e| matches!(e, Event::OrderReject(r) if r.order_id == 2 && r.reason == RejectReason::UnfillableFillOrKill)
+    ));
+    assert!(!events.iter().any(|e| matches!(e, Event::Trade(_))));
+}
+
+#[test]
+fn test_fok_rejects_when_only_same_user_liquidity_is_available() {
+    // The resting sell belongs to the same user as the FOK buy: under the
+    // default `DecrementTake` self-trade behavior that quantity can never
+    // actually trade, so `can_fill_fully` must not count it toward the
+    // pre-check.
+    let engine = new_engine();
+    let maker = limit_order(1, 100, Side::Sell, 10, 50_000);
+    let taker = Order::new(
+        2,
+        100,
+        SYMBOL.to_string(),
+        Side::Buy,
+        OrderType::FillOrKill,
+        10,
+        Some(50_000),
+    );
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(maker),
+            OrderCommand::PlaceOrder(taker),
+        ],
+    );
+
+    assert!(
+        events.iter().any(
+            |e| matches!(e, Event::OrderReject(r) if r.order_id == 2 && r.reason == RejectReason::UnfillableFillOrKill)
+        ),
+        "FOK must not pass its pre-check on its own unreachable resting liquidity: {events:?}"
+    );
+    assert!(!events.iter().any(|e| matches!(e, Event::Trade(_))));
+}
+
+#[test]
+fn test_fok_abort_transaction_rejects_instead_of_partial_filling_behind_own_order() {
+    // Other-user liquidity sums to enough to fill the taker in full, but a
+    // same-user resting order sits ahead of the rest of it in price-time
+    // priority. Under `AbortTransaction`, hitting that order halts matching
+    // entirely (see the `AbortTransaction` arm in `match_order`), so the
+    // pre-check must not count the liquidity behind it as reachable.
+    let engine = new_engine();
+    let other_maker = limit_order(1, 200, Side::Sell, 4, 50_000);
+    let own_maker = limit_order(2, 100, Side::Sell, 4, 50_000);
+    let more_other_liquidity = limit_order(3, 200, Side::Sell, 10, 50_001);
+    let mut taker = Order::new(
+        4,
+        100,
+        SYMBOL.to_string(),
+        Side::Buy,
+        OrderType::FillOrKill,
+        10,
+        Some(50_001),
+    );
+    taker.self_trade_behavior = SelfTradeBehavior::AbortTransaction;
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(other_maker),
+            OrderCommand::PlaceOrder(own_maker),
+            OrderCommand::PlaceOrder(more_other_liquidity),
+            OrderCommand::PlaceOrder(taker),
+        ],
+    );
+
+    assert!(
+        events.iter().any(
+            |e| matches!(e, Event::OrderReject(r) if r.order_id == 4 && r.reason == RejectReason::UnfillableFillOrKill)
+        ),
+        "can_fill_fully must stop counting liquidity behind a same-user AbortTransaction order: {events:?}"
+    );
+    assert!(!events.iter().any(|e| matches!(e, Event::Trade(_))));
+}
+
+// ========== Fee Schedule Tests ==========
+
+#[test]
+fn test_fee_rounding_and_maker_rebate_cap() {
+    // -5 bps maker rebate, 2 bps taker fee on a 10 * 50_000 = 500_000 notional
+    // trade: taker_fee = floor(500_000 * 2 / 10_000) = 100, and the maker
+    // rebate floor(500_000 * -5 / 10_000) = -250 would pay out more than the
+    // taker fee funds, so it must be capped at -100.
+    let mut fee_schedules = HashMap::new();
+    fee_schedules.insert(
+        SYMBOL.to_string(),
+        FeeSchedule {
+            maker_bps: -5,
+            taker_bps: 2,
+        },
+    );
+    let engine = Engine::new(FeeSchedule::default(), fee_schedules);
+
+    let maker = limit_order(1, 200, Side::Sell, 10, 50_000);
+    let taker = limit_order(2, 100, Side::Buy, 10, 50_000);
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(maker),
+            OrderCommand::PlaceOrder(taker),
+        ],
+    );
+
+    let trade = events
+        .iter()
+        .find_map(|e| match e {
+            Event::Trade(trade) => Some(trade),
+            _ => None,
+        })
+        .expect("maker and taker should have crossed");
+    assert_eq!(trade.taker_fee, 100);
+    assert_eq!(trade.maker_fee, -100, "maker rebate must be capped at -taker_fee");
+}
+
+// ========== Bulk Cancel Tests ==========
+
+#[test]
+fn test_cancel_all_for_user_only_cancels_that_users_orders() {
+    let engine = new_engine();
+    let own_order = limit_order(1, 100, Side::Buy, 10, 49_000);
+    let other_order = limit_order(2, 200, Side::Buy, 10, 48_000);
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(own_order),
+            OrderCommand::PlaceOrder(other_order),
+            OrderCommand::CancelAllForUser {
+                user_id: 100,
+                symbol: None,
+            },
+        ],
+    );
+
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, Event::OrderCancelled(c) if c.order_id == 1)));
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, Event::OrderCancelled(c) if c.order_id == 2)));
+    assert!(events.iter().any(
+        |e| matches!(e, Event::BulkCancelSummary(s) if s.user_id == 100 && s.cancelled_count == 1)
+    ));
+}
+
+#[test]
+fn test_cancel_order_ids_cancels_only_the_requested_ids() {
+    let engine = new_engine();
+    let order_a = limit_order(1, 100, Side::Buy, 10, 49_000);
+    let order_b = limit_order(2, 100, Side::Buy, 10, 48_000);
+    let order_c = limit_order(3, 100, Side::Buy, 10, 47_000);
+
+    let events = run_engine(
+        engine,
+        vec![
+            OrderCommand::PlaceOrder(order_a),
+            OrderCommand::PlaceOrder(order_b),
+            OrderCommand::PlaceOrder(order_c),
+            OrderCommand::CancelOrderIds {
+                user_id: 100,
+                order_ids: vec![1, 3],
+            },
+        ],
+    );
+
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, Event::OrderCancelled(c) if c.order_id == 1)));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, Event::OrderCancelled(c) if c.order_id == 3)));
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, Event::OrderCancelled(c) if c.order_id == 2)));
+    assert!(events.iter().any(
+        |e| matches!(e, Event::BulkCancelSummary(s) if s.user_id == 100 && s.cancelled_count == 2)
+    ));
+}
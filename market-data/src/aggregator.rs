@@ -1,24 +1,73 @@
-use crate::types::{DepthEvent, Event, TickerEvent, TradeEvent};
+use crate::types::{
+    BookTickerEvent, DepthEvent, Event, FillUpdateStatus, KlineEvent, KlineInterval, OpenOrder,
+    TickerEvent, TradeEvent, UserOrderUpdateEvent,
+};
 use chrono::Utc;
-use std::collections::HashMap;
+use protocol::types::{OrderId, OrderStatus, Price, PriceLevel, Quantity, UserId};
+use std::collections::{HashMap, VecDeque};
+
+/// Default rolling window for ticker stats: 24 hours, matching a typical
+/// exchange "24h change" ticker.
+const DEFAULT_TICKER_WINDOW_MS: i64 = 24 * 60 * 60 * 1000;
 
 pub struct Aggregator {
     // Depth batching state per symbol
     last_depth: HashMap<String, DepthEvent>,
     last_depth_emit: HashMap<String, i64>,
     depth_interval_ms: i64,
+    // The book state last published on the diff stream per symbol, so the
+    // next periodic emission can be cut down to just the levels that
+    // changed since then.
+    published_depth: HashMap<String, PublishedBook>,
+
+    // The best bid/ask last emitted on the book-ticker stream per symbol
+    // (best_bid, best_bid_qty, best_ask, best_ask_qty), so a BBO unchanged
+    // by the latest depth update doesn't re-emit.
+    last_book_ticker: HashMap<String, (Price, Quantity, Price, Quantity)>,
 
     // Ticker state per symbol
     ticker_state: HashMap<String, TickerState>,
+    ticker_window_ms: i64,
+
+    // Kline bucketing state per (symbol, interval)
+    kline_intervals: Vec<KlineInterval>,
+    candles: HashMap<(String, KlineInterval), Candle>,
+
+    // Order-lifecycle tracking per order_id, for deriving cumulative fill
+    // state across successive `Fill` events.
+    order_tracking: HashMap<OrderId, OrderTrackingState>,
+
+    // Currently-open orders per user, for the `OpenOrders` snapshot attached
+    // alongside each `Ack`/`Fill`/`Cancelled` and pushed in full on subscribe.
+    open_orders: HashMap<UserId, HashMap<OrderId, OpenOrder>>,
 }
 
 impl Aggregator {
     pub fn new() -> Self {
+        Self::with_ticker_window_ms(DEFAULT_TICKER_WINDOW_MS)
+    }
+
+    /// Builds an aggregator whose rolling ticker window spans `window_ms`
+    /// instead of the default 24h, so callers (tests in particular) can
+    /// observe eviction without waiting a full day.
+    pub fn with_ticker_window_ms(window_ms: i64) -> Self {
         Self {
             last_depth: HashMap::new(),
             last_depth_emit: HashMap::new(),
             depth_interval_ms: 100, // 100 ms
+            published_depth: HashMap::new(),
+            last_book_ticker: HashMap::new(),
             ticker_state: HashMap::new(),
+            ticker_window_ms: window_ms,
+            kline_intervals: vec![
+                KlineInterval::OneMinute,
+                KlineInterval::FiveMinutes,
+                KlineInterval::OneHour,
+                KlineInterval::OneDay,
+            ],
+            candles: HashMap::new(),
+            order_tracking: HashMap::new(),
+            open_orders: HashMap::new(),
         }
     }
 
@@ -33,11 +82,20 @@ impl Aggregator {
                 if let Some(ticker) = self.build_ticker_event(t) {
                     out.push(Event::Ticker(ticker));
                 }
+
+                self.update_klines(t, &mut out);
             }
 
             Event::Depth(depth) => {
                 let symbol = depth.symbol.clone();
                 self.last_depth.insert(symbol.clone(), depth.clone());
+
+                // Computed now, independent of the periodic diff throttle
+                // below, so a BBO update isn't held back by it — but pushed
+                // after the depth diff so a subscriber sees book state before
+                // the top-of-book summary derived from it.
+                let book_ticker = self.build_book_ticker_event(depth);
+
                 let now = Utc::now().timestamp_millis();
 
                 let last_emit = self.last_depth_emit.get(&symbol).copied().unwrap_or(0);
@@ -45,17 +103,47 @@ impl Aggregator {
                 if now - last_emit >= self.depth_interval_ms {
                     self.last_depth_emit.insert(symbol.clone(), now);
 
-                    if let Some(latest) = self.last_depth.get(&symbol) {
-                        out.push(Event::Depth(latest.clone()));
+                    if let Some(mut diff) = self.last_depth.get(&symbol).cloned() {
+                        let published = self
+                            .published_depth
+                            .entry(symbol.clone())
+                            .or_insert_with(PublishedBook::default);
+                        diff.bids = diff_levels(&mut published.bids, &diff.bids);
+                        diff.asks = diff_levels(&mut published.asks, &diff.asks);
+                        out.push(Event::Depth(diff));
                     }
                 }
+
+                if let Some(ticker) = book_ticker {
+                    out.push(ticker);
+                }
             }
 
-            Event::OrderUpdate(_) => {
+            Event::OrderUpdate(update) => {
+                let status = self.update_order_tracking(update);
+                self.track_open_order(update);
                 out.push(ev);
+                if let Some(status) = status {
+                    out.push(Event::OrderUpdate(status));
+                }
+
+                // Ack/Fill/Cancelled are the transitions that change what's
+                // open, so each carries a fresh aggregate snapshot alongside
+                // it; a client never has to replay history to know what it
+                // still has resting.
+                if matches!(
+                    update,
+                    UserOrderUpdateEvent::Ack { .. }
+                        | UserOrderUpdateEvent::Fill { .. }
+                        | UserOrderUpdateEvent::Cancelled { .. }
+                ) {
+                    out.push(Event::OrderUpdate(
+                        self.open_orders_event(update.user_id()),
+                    ));
+                }
             }
 
-            Event::Ticker(_) => {
+            Event::Ticker(_) | Event::BookTicker(_) | Event::Kline(_) | Event::SymbolInfo(_) => {
                 out.push(ev);
             }
         }
@@ -63,83 +151,606 @@ impl Aggregator {
         out
     }
 
-    fn update_ticker_from_trade(&mut self, t: &TradeEvent) {
-        let symbol = &t.symbol;
-        let state = self
-            .ticker_state
-            .entry(symbol.clone())
-            .or_insert_with(TickerState::new);
+    /// Folds a `Fill`/`Cancelled`/`Reject` into this order's tracked
+    /// cumulative state and returns the resulting authoritative `Status`
+    /// event, evicting the tracked state once the order reaches a terminal
+    /// outcome (fully filled, cancelled, or rejected). `Ack` carries no
+    /// quantity information to fold in, so it produces no `Status`.
+    fn update_order_tracking(&mut self, update: &UserOrderUpdateEvent) -> Option<UserOrderUpdateEvent> {
+        match update {
+            UserOrderUpdateEvent::Fill {
+                order_id,
+                user_id,
+                symbol,
+                trade_id,
+                filled_quantity,
+                filled_price,
+                remaining_quantity,
+                ..
+            } => {
+                let state = self
+                    .order_tracking
+                    .entry(*order_id)
+                    .or_insert_with(|| OrderTrackingState::new(*user_id, symbol.clone()));
+
+                state.record_fill(*trade_id, *filled_quantity, *filled_price, *remaining_quantity);
 
-        let price = t.price;
-        let qty = t.quantity;
-        let ts = t.timestamp;
+                let status = UserOrderUpdateEvent::Status {
+                    order_id: *order_id,
+                    user_id: *user_id,
+                    symbol: symbol.clone(),
+                    cumulative_filled: state.cumulative_filled,
+                    remaining: *remaining_quantity,
+                    average_fill_price: state.average_fill_price(),
+                    state: if *remaining_quantity == 0 {
+                        OrderStatus::Filled
+                    } else {
+                        OrderStatus::PartiallyFilled
+                    },
+                    // Stamped by `MarketDataPipeline` just before publishing.
+                    seq: 0,
+                };
 
-        // Initialize open/high/low with first trade
-        if state.open_24h.is_none() {
-            state.open_24h = Some(price);
-            state.high_24h = price;
-            state.low_24h = price;
+                if *remaining_quantity == 0 {
+                    self.order_tracking.remove(order_id);
+                }
+
+                Some(status)
+            }
+
+            UserOrderUpdateEvent::Cancelled {
+                order_id,
+                user_id,
+                symbol,
+                ..
+            } => {
+                let tracked = self.order_tracking.remove(order_id);
+                Some(UserOrderUpdateEvent::Status {
+                    order_id: *order_id,
+                    user_id: *user_id,
+                    symbol: symbol.clone(),
+                    cumulative_filled: tracked.as_ref().map_or(0, |s| s.cumulative_filled),
+                    remaining: 0,
+                    average_fill_price: tracked.as_ref().and_then(|s| s.average_fill_price()),
+                    state: OrderStatus::Cancelled,
+                    // Stamped by `MarketDataPipeline` just before publishing.
+                    seq: 0,
+                })
+            }
+
+            UserOrderUpdateEvent::Reject {
+                order_id,
+                user_id,
+                symbol,
+                ..
+            } => {
+                self.order_tracking.remove(order_id);
+                Some(UserOrderUpdateEvent::Status {
+                    order_id: *order_id,
+                    user_id: *user_id,
+                    symbol: symbol.clone(),
+                    cumulative_filled: 0,
+                    remaining: 0,
+                    average_fill_price: None,
+                    state: OrderStatus::Rejected,
+                    // Stamped by `MarketDataPipeline` just before publishing.
+                    seq: 0,
+                })
+            }
+
+            UserOrderUpdateEvent::Ack { .. }
+            | UserOrderUpdateEvent::Triggered { .. }
+            | UserOrderUpdateEvent::Status { .. }
+            | UserOrderUpdateEvent::BulkCancelled { .. }
+            | UserOrderUpdateEvent::OpenOrders { .. } => None,
         }
+    }
+
+    /// Folds `update` into `open_orders`: `Ack` opens a new order, `Fill`
+    /// refreshes its cumulative fill state (or closes it out once fully
+    /// filled), and `Cancelled`/`Reject` close it regardless of how much had
+    /// filled. Called after `update_order_tracking`, so a `Fill`'s `Status`
+    /// derivation has already folded this fill into `order_tracking` by the
+    /// time this reads it back.
+    fn track_open_order(&mut self, update: &UserOrderUpdateEvent) {
+        match update {
+            UserOrderUpdateEvent::Ack {
+                order_id,
+                user_id,
+                symbol,
+                order_type,
+                trigger_price,
+                timestamp,
+                ..
+            } => {
+                self.open_orders.entry(*user_id).or_default().insert(
+                    *order_id,
+                    OpenOrder {
+                        order_id: *order_id,
+                        symbol: symbol.clone(),
+                        order_type: *order_type,
+                        trigger_price: *trigger_price,
+                        cumulative_filled: 0,
+                        average_fill_price: None,
+                        opened_at: *timestamp,
+                    },
+                );
+            }
 
-        state.last_price = Some(price);
-        state.last_trade_time = ts;
+            UserOrderUpdateEvent::Fill {
+                order_id,
+                user_id,
+                remaining_quantity,
+                ..
+            } => {
+                if *remaining_quantity == 0 {
+                    self.close_open_order(*user_id, order_id);
+                    return;
+                }
 
-        state.volume_24h = state.volume_24h.saturating_add(qty);
+                let average_fill_price = self
+                    .order_tracking
+                    .get(order_id)
+                    .and_then(|s| s.average_fill_price());
+                let cumulative_filled = self
+                    .order_tracking
+                    .get(order_id)
+                    .map_or(0, |s| s.cumulative_filled);
 
-        if state.open_24h.is_none() {
-            state.open_24h = Some(price);
+                if let Some(orders) = self.open_orders.get_mut(user_id) {
+                    if let Some(order) = orders.get_mut(order_id) {
+                        order.cumulative_filled = cumulative_filled;
+                        order.average_fill_price = average_fill_price;
+                    }
+                }
+            }
+
+            UserOrderUpdateEvent::Cancelled {
+                order_id, user_id, ..
+            }
+            | UserOrderUpdateEvent::Reject {
+                order_id, user_id, ..
+            } => {
+                self.close_open_order(*user_id, order_id);
+            }
+
+            UserOrderUpdateEvent::Triggered { .. }
+            | UserOrderUpdateEvent::Status { .. }
+            | UserOrderUpdateEvent::BulkCancelled { .. }
+            | UserOrderUpdateEvent::OpenOrders { .. } => {}
         }
+    }
 
-        if price > state.high_24h {
-            state.high_24h = price;
+    /// Removes `order_id` from `user_id`'s open-order set, dropping the set
+    /// entirely once it's empty so stale users don't accumulate in the map.
+    fn close_open_order(&mut self, user_id: UserId, order_id: &OrderId) {
+        if let Some(orders) = self.open_orders.get_mut(&user_id) {
+            orders.remove(order_id);
+            if orders.is_empty() {
+                self.open_orders.remove(&user_id);
+            }
         }
-        if price < state.low_24h {
-            state.low_24h = price;
+    }
+
+    /// Builds the `OpenOrders` snapshot event attached alongside an
+    /// `Ack`/`Fill`/`Cancelled` and pushed in full on subscribe.
+    fn open_orders_event(&self, user_id: UserId) -> UserOrderUpdateEvent {
+        UserOrderUpdateEvent::OpenOrders {
+            user_id,
+            symbol: String::new(),
+            orders: self.open_orders_snapshot(user_id),
+            timestamp: Utc::now().timestamp_millis(),
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
         }
     }
 
-    fn build_ticker_event(&self, t: &TradeEvent) -> Option<TickerEvent> {
-        let state = self.ticker_state.get(&t.symbol)?;
-        if state.last_price.is_none() || state.open_24h.is_none() {
+    /// `user_id`'s currently-open orders, sorted by `order_id` for a
+    /// deterministic wire order, for a client that just (re)subscribed to
+    /// its private order-update stream and needs a baseline before it can
+    /// make sense of subsequent incremental frames.
+    pub fn open_orders_snapshot(&self, user_id: UserId) -> Vec<OpenOrder> {
+        let mut orders: Vec<OpenOrder> = self
+            .open_orders
+            .get(&user_id)
+            .map(|by_order| by_order.values().cloned().collect())
+            .unwrap_or_default();
+        orders.sort_by_key(|o| o.order_id);
+        orders
+    }
+
+    /// Undoes a previously published fill identified by `(order_id,
+    /// trade_id)` — e.g. a reorg'd or rejected settlement — by re-emitting
+    /// the identical `Fill` event body with `status: FillUpdateStatus::
+    /// Revoke` so a consumer can subtract what it had added, followed by
+    /// the order's updated `Status`. Returns `None` if no such fill is on
+    /// record: it may have already been revoked, or the order may have
+    /// since fully filled and had its tracking state evicted, in which
+    /// case the fill can no longer be undone.
+    pub fn revoke_fill(&mut self, order_id: OrderId, trade_id: u64) -> Option<Vec<Event>> {
+        let state = self.order_tracking.get_mut(&order_id)?;
+        let record = state.revoke_fill(trade_id)?;
+
+        let user_id = state.user_id;
+        let symbol = state.symbol.clone();
+        let cumulative_filled = state.cumulative_filled;
+        let average_fill_price = state.average_fill_price();
+
+        // Remaining quantity only shrinks as an order fills, so the smallest
+        // `remaining_quantity` among the revoked fill and those still
+        // standing is what the order's latest fill reported; revoking gives
+        // back the quantity the revoked fill had consumed on top of that.
+        let remaining = state
+            .fills
+            .iter()
+            .map(|f| f.remaining_quantity)
+            .fold(record.remaining_quantity, |a, b| a.min(b))
+            + record.filled_quantity;
+
+        let revoke = UserOrderUpdateEvent::Fill {
+            order_id,
+            user_id,
+            symbol: symbol.clone(),
+            trade_id,
+            status: FillUpdateStatus::Revoke,
+            filled_quantity: record.filled_quantity,
+            filled_price: record.filled_price,
+            remaining_quantity: record.remaining_quantity,
+            timestamp: Utc::now().timestamp_millis(),
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
+        };
+
+        let status = UserOrderUpdateEvent::Status {
+            order_id,
+            user_id,
+            symbol,
+            cumulative_filled,
+            remaining,
+            average_fill_price,
+            state: if cumulative_filled == 0 {
+                OrderStatus::Pending
+            } else {
+                OrderStatus::PartiallyFilled
+            },
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
+        };
+
+        Some(vec![Event::OrderUpdate(revoke), Event::OrderUpdate(status)])
+    }
+
+    /// The latest cached full order book for `symbol`, for a client that
+    /// just (re)subscribed and needs a snapshot to apply subsequent diffs
+    /// on top of. `is_snapshot`/`first_seq`/`last_seq` are filled in by
+    /// `MarketDataPipeline`, which owns sequence numbering.
+    pub fn depth_snapshot(&self, symbol: &str) -> Option<DepthEvent> {
+        self.last_depth.get(symbol).cloned()
+    }
+
+    /// Derives the best-bid/offer from `depth`'s top-of-book and returns a
+    /// `BookTickerEvent` only if it differs from the last one emitted for
+    /// this symbol — a depth tick that only touches deeper levels shouldn't
+    /// spam a BBO subscriber. `None` if either side of the book is empty:
+    /// there's no top-of-book to report yet.
+    fn build_book_ticker_event(&mut self, depth: &DepthEvent) -> Option<Event> {
+        let best_bid = depth.bids.iter().max_by_key(|l| l.price)?;
+        let best_ask = depth.asks.iter().min_by_key(|l| l.price)?;
+
+        let current = (best_bid.price, best_bid.quantity, best_ask.price, best_ask.quantity);
+        if self.last_book_ticker.get(&depth.symbol) == Some(&current) {
             return None;
         }
+        self.last_book_ticker.insert(depth.symbol.clone(), current);
+
+        Some(Event::BookTicker(BookTickerEvent {
+            symbol: depth.symbol.clone(),
+            best_bid: best_bid.price,
+            best_bid_qty: best_bid.quantity,
+            best_ask: best_ask.price,
+            best_ask_qty: best_ask.quantity,
+            timestamp: depth.timestamp,
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
+        }))
+    }
+
+    fn update_ticker_from_trade(&mut self, t: &TradeEvent) {
+        let state = self
+            .ticker_state
+            .entry(t.symbol.clone())
+            .or_insert_with(TickerState::new);
+
+        state.push_and_evict(t.timestamp, t.price, t.quantity, self.ticker_window_ms);
+    }
+
+    /// Builds a `TickerEvent` summarizing the retained rolling window:
+    /// `open` is the oldest retained trade's price, `high`/`low`/`volume`
+    /// are read off `TickerState`'s incrementally maintained running totals,
+    /// and `price_change*` is relative to `open`. Returns `None` only if the
+    /// window is empty, which shouldn't happen right after
+    /// `update_ticker_from_trade` pushed `t` into it.
+    fn build_ticker_event(&self, t: &TradeEvent) -> Option<TickerEvent> {
+        let state = self.ticker_state.get(&t.symbol)?;
+        let open = state.trades.front()?.1;
+        let last_price = state.trades.back()?.1;
+        let high = state.high()?;
+        let low = state.low()?;
+        let volume = state.volume;
+
+        let price_change = last_price as i64 - open as i64;
 
         Some(TickerEvent {
             symbol: t.symbol.clone(),
-            last_price: state.last_price.unwrap(),
-            open: state.open_24h.unwrap(),
-            high: state.high_24h,
-            low: state.low_24h,
-            volume: state.volume_24h,
-            price_change: state.last_price.unwrap() as i64 - state.open_24h.unwrap() as i64,
-            price_change_percent: ((state.last_price.unwrap() as i64
-                - state.open_24h.unwrap() as i64) as f64
-                / state.open_24h.unwrap() as f64)
-                * 100.0,
+            last_price,
+            open,
+            high,
+            low,
+            volume,
+            price_change,
+            price_change_percent: price_change as f64 / open as f64 * 100.0,
             timestamp: Utc::now().timestamp_millis(),
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
         })
     }
+
+    /// Rolls `t` into each configured interval's current candle, emitting
+    /// one `Kline` event per interval: just the updated in-progress bucket
+    /// if `t` still falls inside it, or the just-finished bucket (`closed:
+    /// true`) followed by the freshly opened one if `t` starts a new one.
+    fn update_klines(&mut self, t: &TradeEvent, out: &mut Vec<Event>) {
+        for interval in self.kline_intervals.clone() {
+            let bucket_start = (t.timestamp / interval.as_millis()) * interval.as_millis();
+            let key = (t.symbol.clone(), interval);
+
+            match self.candles.get_mut(&key) {
+                Some(candle) if candle.bucket_start == bucket_start => {
+                    candle.update(t.price, t.quantity);
+                    out.push(Event::Kline(candle.to_event(&t.symbol, interval, false)));
+                }
+                Some(candle) => {
+                    out.push(Event::Kline(candle.to_event(&t.symbol, interval, true)));
+                    let fresh = Candle::new(bucket_start, t.price, t.quantity);
+                    out.push(Event::Kline(fresh.to_event(&t.symbol, interval, false)));
+                    self.candles.insert(key, fresh);
+                }
+                None => {
+                    let fresh = Candle::new(bucket_start, t.price, t.quantity);
+                    out.push(Event::Kline(fresh.to_event(&t.symbol, interval, false)));
+                    self.candles.insert(key, fresh);
+                }
+            }
+        }
+    }
 }
 
+/// In-progress OHLCV bucket for one `(symbol, interval)` pair.
+#[derive(Debug, Clone)]
+struct Candle {
+    bucket_start: i64,
+    open: Price,
+    high: Price,
+    low: Price,
+    close: Price,
+    volume: Quantity,
+    trade_count: u64,
+}
+
+impl Candle {
+    fn new(bucket_start: i64, price: Price, quantity: Quantity) -> Self {
+        Self {
+            bucket_start,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: quantity,
+            trade_count: 1,
+        }
+    }
+
+    fn update(&mut self, price: Price, quantity: Quantity) {
+        self.close = price;
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.volume = self.volume.saturating_add(quantity);
+        self.trade_count += 1;
+    }
+
+    fn to_event(&self, symbol: &str, interval: KlineInterval, closed: bool) -> KlineEvent {
+        KlineEvent {
+            symbol: symbol.to_string(),
+            interval,
+            open_time: self.bucket_start,
+            close_time: self.bucket_start + interval.as_millis() - 1,
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+            trade_count: self.trade_count,
+            closed,
+        }
+    }
+}
+
+/// A symbol's trade tape retained within the rolling ticker window, as
+/// `(timestamp, price, quantity)` triples in arrival order, plus the running
+/// totals `build_ticker_event` reads so it never has to rescan the window.
+///
+/// `volume` is a plain running sum (added on push, subtracted on eviction).
+/// `high_deque`/`low_deque` are classic monotonic sliding-window-maximum
+/// deques: `high_deque` is kept non-increasing by price (so its front is
+/// always the window's high) and `low_deque` non-decreasing (front is the
+/// low), each storing only the `(timestamp, price)` pairs that could still
+/// become the extreme as older entries expire — every other entry is popped
+/// off the back the moment a new trade proves it can never win again. This
+/// makes both `high()`/`low()` O(1) and keeps `push_and_evict` O(1)
+/// amortized, since each trade is pushed and popped at most once across the
+/// deque's lifetime.
 #[derive(Debug, Clone)]
 struct TickerState {
-    last_price: Option<u64>,
-    open_24h: Option<u64>,
-    high_24h: u64,
-    low_24h: u64,
-    volume_24h: u64,
-    last_trade_time: i64,
+    trades: VecDeque<(i64, Price, Quantity)>,
+    volume: Quantity,
+    high_deque: VecDeque<(i64, Price)>,
+    low_deque: VecDeque<(i64, Price)>,
 }
 
 impl TickerState {
     fn new() -> Self {
         Self {
-            last_price: None,
-            open_24h: None,
-            high_24h: 0,
-            low_24h: u64::MAX,
-            volume_24h: 0,
-            last_trade_time: 0,
+            trades: VecDeque::new(),
+            volume: 0,
+            high_deque: VecDeque::new(),
+            low_deque: VecDeque::new(),
+        }
+    }
+
+    /// Appends `(ts, price, quantity)`, then evicts every entry older than
+    /// `ts - window_ms` from the front.
+    fn push_and_evict(&mut self, ts: i64, price: Price, quantity: Quantity, window_ms: i64) {
+        self.trades.push_back((ts, price, quantity));
+        self.volume = self.volume.saturating_add(quantity);
+
+        while matches!(self.high_deque.back(), Some(&(_, back)) if back <= price) {
+            self.high_deque.pop_back();
+        }
+        self.high_deque.push_back((ts, price));
+
+        while matches!(self.low_deque.back(), Some(&(_, back)) if back >= price) {
+            self.low_deque.pop_back();
+        }
+        self.low_deque.push_back((ts, price));
+
+        let cutoff = ts - window_ms;
+        while let Some(&(oldest_ts, _, oldest_qty)) = self.trades.front() {
+            if oldest_ts < cutoff {
+                self.trades.pop_front();
+                self.volume = self.volume.saturating_sub(oldest_qty);
+            } else {
+                break;
+            }
+        }
+        while matches!(self.high_deque.front(), Some(&(ts, _)) if ts < cutoff) {
+            self.high_deque.pop_front();
+        }
+        while matches!(self.low_deque.front(), Some(&(ts, _)) if ts < cutoff) {
+            self.low_deque.pop_front();
         }
     }
+
+    /// The window's highest trade price, O(1).
+    fn high(&self) -> Option<Price> {
+        self.high_deque.front().map(|&(_, price)| price)
+    }
+
+    /// The window's lowest trade price, O(1).
+    fn low(&self) -> Option<Price> {
+        self.low_deque.front().map(|&(_, price)| price)
+    }
+}
+
+/// One order's accumulated execution so far: how much has filled in total,
+/// enough of the `(price, quantity)` history to derive the volume-weighted
+/// average fill price, and the individual `fills` published for it so a
+/// later `Revoke` can be matched back to the `New` it undoes.
+#[derive(Debug, Clone)]
+struct OrderTrackingState {
+    user_id: UserId,
+    symbol: String,
+    cumulative_filled: Quantity,
+    // sum(filled_price * filled_quantity); wide enough that it can't
+    // overflow before `cumulative_filled` (a u64) does.
+    total_notional: u128,
+    fills: Vec<FillRecord>,
+}
+
+impl OrderTrackingState {
+    fn new(user_id: UserId, symbol: String) -> Self {
+        Self {
+            user_id,
+            symbol,
+            cumulative_filled: 0,
+            total_notional: 0,
+            fills: Vec::new(),
+        }
+    }
+
+    fn record_fill(
+        &mut self,
+        trade_id: u64,
+        filled_quantity: Quantity,
+        filled_price: Price,
+        remaining_quantity: Quantity,
+    ) {
+        self.cumulative_filled = self.cumulative_filled.saturating_add(filled_quantity);
+        self.total_notional += filled_price as u128 * filled_quantity as u128;
+        self.fills.push(FillRecord {
+            trade_id,
+            filled_quantity,
+            filled_price,
+            remaining_quantity,
+        });
+    }
+
+    fn average_fill_price(&self) -> Option<Price> {
+        if self.cumulative_filled == 0 {
+            return None;
+        }
+        Some((self.total_notional / self.cumulative_filled as u128) as Price)
+    }
+
+    /// Removes and returns the fill matching `trade_id`, reversing its
+    /// contribution to `cumulative_filled`/`total_notional`. `None` if no
+    /// such fill is on record.
+    fn revoke_fill(&mut self, trade_id: u64) -> Option<FillRecord> {
+        let pos = self.fills.iter().position(|f| f.trade_id == trade_id)?;
+        let record = self.fills.remove(pos);
+        self.cumulative_filled = self.cumulative_filled.saturating_sub(record.filled_quantity);
+        self.total_notional -= record.filled_price as u128 * record.filled_quantity as u128;
+        Some(record)
+    }
+}
+
+/// One published `Fill`'s execution details, kept around so a later
+/// `Revoke` can undo exactly what it added.
+#[derive(Debug, Clone, Copy)]
+struct FillRecord {
+    trade_id: u64,
+    filled_quantity: Quantity,
+    filled_price: Price,
+    remaining_quantity: Quantity,
+}
+
+/// A symbol's order book as last sent out on the depth diff stream, kept so
+/// the next periodic emission can be reduced to just what changed.
+#[derive(Debug, Clone, Default)]
+struct PublishedBook {
+    bids: HashMap<Price, Quantity>,
+    asks: HashMap<Price, Quantity>,
+}
+
+/// Diffs `current` against `published`, returning only the levels whose
+/// quantity changed (including ones newly appearing) plus a `0`-quantity
+/// entry for every level `published` had that `current` no longer does,
+/// then updates `published` to match `current`.
+fn diff_levels(published: &mut HashMap<Price, Quantity>, current: &[PriceLevel]) -> Vec<PriceLevel> {
+    let mut diff = Vec::new();
+
+    for level in current {
+        if published.get(&level.price) != Some(&level.quantity) {
+            diff.push(*level);
+        }
+    }
+
+    for &price in published.keys() {
+        if !current.iter().any(|level| level.price == price) {
+            diff.push(PriceLevel { price, quantity: 0 });
+        }
+    }
+
+    *published = current.iter().map(|level| (level.price, level.quantity)).collect();
+
+    diff
 }
@@ -1,6 +1,7 @@
 pub mod aggregator;
 pub mod pipeline;
 pub mod publisher;
+pub mod subscription;
 pub mod transformer;
 pub mod types;
 
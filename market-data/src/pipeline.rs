@@ -0,0 +1,318 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::Utc;
+use crossbeam_channel::Receiver;
+use protocol::types::{Event as EngineEvent, UserId};
+
+use crate::aggregator::Aggregator;
+use crate::publisher::publisher::Publisher;
+use crate::transformer::Transformer;
+use crate::types::{DepthEvent, Event, UserOrderUpdateEvent};
+
+/// Default bound on the number of not-yet-published WS events buffered
+/// before backpressure kicks in.
+const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Default staleness deadline (ms): a conflatable event still queued this
+/// long after it was enqueued is dropped rather than published, since a
+/// depth/ticker update that stale is no longer useful to a subscriber.
+const DEFAULT_MAX_STALENESS_MS: i64 = 5_000;
+
+/// Backpressure counters an operator can use to observe how much
+/// load-shedding the pipeline is doing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineStats {
+    pub dropped: u64,
+    pub conflated: u64,
+    pub published: u64,
+}
+
+struct QueuedEvent {
+    seq: u64,
+    event: Event,
+    enqueued_at_ms: i64,
+}
+
+/// `Depth`/`Ticker` updates may be conflated or evicted under backpressure;
+/// everything else (`Trade`, `OrderUpdate`, `Kline`, `SymbolInfo`) is never
+/// dropped.
+fn is_droppable(event: &Event) -> bool {
+    matches!(event, Event::Depth(_) | Event::Ticker(_))
+}
+
+/// The `(channel, symbol)` a droppable event conflates on: a newer update
+/// for the same key replaces the older one in the queue instead of both
+/// being kept.
+fn conflation_key(event: &Event) -> Option<(&'static str, String)> {
+    match event {
+        Event::Depth(d) => Some(("depth", d.symbol.clone())),
+        Event::Ticker(t) => Some(("ticker", t.symbol.clone())),
+        _ => None,
+    }
+}
+
+/// Consumes engine events off a channel, transforms and aggregates them into
+/// WS events, and fans each one out to every registered publisher whose
+/// subscriptions match it.
+///
+/// Produced WS events pass through a capacity-bounded internal queue first.
+/// `Trade` and `OrderUpdate` events are never dropped; `Depth` and `Ticker`
+/// updates for the same symbol are conflated in place (a newer one replaces
+/// the queued-but-unpublished older one) and, failing that, the oldest
+/// droppable entry is evicted to make room under a full queue. Any
+/// conflatable entry still queued past `max_staleness_ms` is dropped instead
+/// of published.
+pub struct MarketDataPipeline {
+    publishers: Vec<Box<dyn Publisher>>,
+    transformer: Transformer,
+    aggregator: Aggregator,
+    capacity: usize,
+    max_staleness_ms: i64,
+    queue: VecDeque<QueuedEvent>,
+    pending_conflatable: HashMap<(&'static str, String), u64>,
+    next_seq: u64,
+    stats: PipelineStats,
+
+    // Per-(channel, key) monotonic sequence numbers stamped onto outgoing
+    // `Trade`/`Ticker`/`OrderUpdate` events right before they're published.
+    stream_seq: HashMap<(&'static str, String), u64>,
+
+    // Per-symbol count of every depth update this pipeline has ever seen
+    // (whether or not it survived conflation), used as the `first_seq`/
+    // `last_seq` range stamped onto depth diffs and snapshots.
+    depth_raw_seq: HashMap<String, u64>,
+}
+
+impl MarketDataPipeline {
+    pub fn new(publishers: Vec<Box<dyn Publisher>>) -> Self {
+        Self::with_capacity(publishers, DEFAULT_CAPACITY, DEFAULT_MAX_STALENESS_MS)
+    }
+
+    /// Builds a pipeline with an explicit backpressure capacity and max
+    /// staleness deadline (ms) for conflatable events.
+    pub fn with_capacity(
+        publishers: Vec<Box<dyn Publisher>>,
+        capacity: usize,
+        max_staleness_ms: i64,
+    ) -> Self {
+        Self {
+            publishers,
+            transformer: Transformer::new(),
+            aggregator: Aggregator::new(),
+            capacity,
+            max_staleness_ms,
+            queue: VecDeque::new(),
+            pending_conflatable: HashMap::new(),
+            next_seq: 0,
+            stats: PipelineStats::default(),
+            stream_seq: HashMap::new(),
+            depth_raw_seq: HashMap::new(),
+        }
+    }
+
+    pub fn stats(&self) -> PipelineStats {
+        self.stats
+    }
+
+    /// A full order-book snapshot for `symbol`, for a client that just
+    /// (re)subscribed: `first_seq` is always `1` and `last_seq` is the
+    /// latest depth sequence number seen so far, so the caller can confirm
+    /// the next depth diff it receives continues from `last_seq`.
+    pub fn depth_snapshot(&mut self, symbol: &str) -> Option<Event> {
+        let mut snapshot = self.aggregator.depth_snapshot(symbol)?;
+        let last_seq = self.depth_raw_seq.get(symbol).copied().unwrap_or(0);
+        snapshot.is_snapshot = true;
+        snapshot.first_seq = 1;
+        snapshot.last_seq = last_seq;
+        snapshot.seq = last_seq;
+        Some(Event::Depth(snapshot))
+    }
+
+    /// A full open-orders snapshot for `user_id`, for a client that just
+    /// (re)subscribed to its private order-update stream and needs a
+    /// baseline before it can make sense of subsequent incremental
+    /// `Ack`/`Fill`/`Cancelled` frames. Always returns a snapshot, even if
+    /// `user_id` has no open orders: an empty snapshot is still meaningful.
+    pub fn open_orders_snapshot(&mut self, user_id: UserId) -> Event {
+        let orders = self.aggregator.open_orders_snapshot(user_id);
+        let seq = self.next_stream_seq("order_update", format!("{}:", user_id));
+        Event::OrderUpdate(UserOrderUpdateEvent::OpenOrders {
+            user_id,
+            symbol: String::new(),
+            orders,
+            timestamp: Utc::now().timestamp_millis(),
+            seq,
+        })
+    }
+
+    /// Runs until `event_rx` is closed, enqueuing and then draining every WS
+    /// event produced from each incoming engine event.
+    pub fn run(&mut self, event_rx: Receiver<EngineEvent>) {
+        while let Ok(event) = event_rx.recv() {
+            let ws_event = self.transformer.transform(event);
+            let outputs = self.aggregator.process(ws_event);
+
+            for out in outputs {
+                self.enqueue(out);
+            }
+
+            self.drain();
+        }
+    }
+
+    /// Adds `event` to the outbound queue, applying conflation and
+    /// capacity-based eviction for droppable event kinds.
+    pub fn enqueue(&mut self, mut event: Event) {
+        let now = Utc::now().timestamp_millis();
+
+        if let Event::Depth(depth) = &mut event {
+            self.stamp_depth_range(depth);
+        }
+
+        let Some(key) = conflation_key(&event) else {
+            // Trade / OrderUpdate: never dropped, exempt from the cap.
+            let seq = self.next_seq();
+            self.queue.push_back(QueuedEvent {
+                seq,
+                event,
+                enqueued_at_ms: now,
+            });
+            return;
+        };
+
+        if let Some(&seq) = self.pending_conflatable.get(&key) {
+            if let Some(queued) = self.queue.iter_mut().find(|q| q.seq == seq) {
+                queued.event = event;
+                queued.enqueued_at_ms = now;
+                self.stats.conflated += 1;
+                return;
+            }
+            // The previously tracked entry was already drained; fall
+            // through and enqueue this one fresh.
+        }
+
+        if self.queue.len() >= self.capacity && !self.evict_oldest_droppable() {
+            // Full, and nothing droppable to evict to make room: shed it.
+            self.stats.dropped += 1;
+            return;
+        }
+
+        let seq = self.next_seq();
+        self.pending_conflatable.insert(key, seq);
+        self.queue.push_back(QueuedEvent {
+            seq,
+            event,
+            enqueued_at_ms: now,
+        });
+    }
+
+    /// Publishes every queued event whose staleness deadline hasn't passed
+    /// to the publishers subscribed to it, and drops the rest.
+    pub fn drain(&mut self) {
+        let now = Utc::now().timestamp_millis();
+
+        while let Some(queued) = self.queue.pop_front() {
+            if let Some(key) = conflation_key(&queued.event) {
+                if self.pending_conflatable.get(&key) == Some(&queued.seq) {
+                    self.pending_conflatable.remove(&key);
+                }
+            }
+
+            if is_droppable(&queued.event) && now - queued.enqueued_at_ms > self.max_staleness_ms {
+                self.stats.dropped += 1;
+                continue;
+            }
+
+            let mut event = queued.event;
+            self.stamp_seq(&mut event);
+            self.publish(&event);
+        }
+    }
+
+    fn next_seq(&mut self) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        seq
+    }
+
+    /// Sets `depth.first_seq`/`last_seq` to the range of raw depth updates
+    /// this event covers: `last_seq` is always this update's own place in
+    /// `depth_raw_seq`, and `first_seq` carries forward from whatever entry
+    /// is still queued for the same symbol (so conflating several updates
+    /// into one published event doesn't lose the range it subsumes).
+    fn stamp_depth_range(&mut self, depth: &mut DepthEvent) {
+        let raw_seq = {
+            let counter = self.depth_raw_seq.entry(depth.symbol.clone()).or_insert(0);
+            *counter += 1;
+            *counter
+        };
+
+        let carried_first_seq = self
+            .pending_conflatable
+            .get(&("depth", depth.symbol.clone()))
+            .and_then(|seq| self.queue.iter().find(|q| q.seq == *seq))
+            .and_then(|q| match &q.event {
+                Event::Depth(prev) => Some(prev.first_seq),
+                _ => None,
+            });
+
+        depth.first_seq = carried_first_seq.unwrap_or(raw_seq);
+        depth.last_seq = raw_seq;
+    }
+
+    /// Stamps the per-(channel, key) sequence number a publisher sees on
+    /// `event`, right before it's delivered. `Depth`'s `seq` mirrors
+    /// `last_seq`, already computed by `stamp_depth_range` at enqueue time;
+    /// `Trade`, `Ticker`, `BookTicker` and `OrderUpdate` get a plain
+    /// incrementing counter, and `Kline`/`SymbolInfo` carry no `seq` and are
+    /// left as they are.
+    fn stamp_seq(&mut self, event: &mut Event) {
+        match event {
+            Event::Trade(t) => t.seq = self.next_stream_seq("trade", t.symbol.clone()),
+            Event::Ticker(t) => t.seq = self.next_stream_seq("ticker", t.symbol.clone()),
+            Event::BookTicker(b) => b.seq = self.next_stream_seq("book_ticker", b.symbol.clone()),
+            Event::Depth(d) => d.seq = d.last_seq,
+            Event::OrderUpdate(update) => {
+                let key = format!("{}:{}", update.user_id(), update.symbol());
+                let seq = self.next_stream_seq("order_update", key);
+                update.set_seq(seq);
+            }
+            Event::Kline(_) => {}
+            Event::SymbolInfo(_) => {}
+        }
+    }
+
+    fn next_stream_seq(&mut self, channel: &'static str, key: String) -> u64 {
+        let counter = self.stream_seq.entry((channel, key)).or_insert(0);
+        *counter += 1;
+        *counter
+    }
+
+    /// Evicts the oldest droppable (`Depth`/`Ticker`) entry in the queue,
+    /// returning whether one was found.
+    fn evict_oldest_droppable(&mut self) -> bool {
+        let Some(pos) = self.queue.iter().position(|q| is_droppable(&q.event)) else {
+            return false;
+        };
+
+        let removed = self.queue.remove(pos).expect("pos came from this queue");
+        if let Some(key) = conflation_key(&removed.event) {
+            self.pending_conflatable.remove(&key);
+        }
+        self.stats.dropped += 1;
+        true
+    }
+
+    fn publish(&mut self, event: &Event) {
+        let mut delivered = false;
+        for publisher in &self.publishers {
+            if publisher.is_subscribed(event) {
+                publisher.publish(event);
+                delivered = true;
+            }
+        }
+        if delivered {
+            self.stats.published += 1;
+        }
+    }
+}
@@ -0,0 +1,28 @@
+pub mod publisher {
+    use crate::subscription::Subscription;
+    use crate::types::Event;
+
+    /// Sink for outbound WS events. Implementations own the transport (a
+    /// broadcast channel, a websocket fan-out, a test double) and decide how
+    /// to deliver `Event`s to whatever is subscribed to them.
+    ///
+    /// `subscribe`/`unsubscribe`/`is_subscribed` default to an "everything"
+    /// subscription, so a `Publisher` that never calls `subscribe` keeps the
+    /// original fan-out-to-all behavior. Implementations that want real
+    /// per-client routing track their own registered `Subscription`s (e.g.
+    /// behind a `Mutex`, since these methods take `&self`) and override all
+    /// three.
+    pub trait Publisher: Send + Sync {
+        fn publish(&self, event: &Event);
+        fn publish_batch(&self, events: Vec<Event>);
+
+        fn subscribe(&self, _subscription: Subscription) {}
+        fn unsubscribe(&self, _subscription: &Subscription) {}
+
+        /// Whether `event` matches one of this publisher's registered
+        /// subscriptions.
+        fn is_subscribed(&self, _event: &Event) -> bool {
+            true
+        }
+    }
+}
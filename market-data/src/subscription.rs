@@ -0,0 +1,87 @@
+use crate::types::Event;
+use protocol::types::UserId;
+
+/// The kind of WS event a `Subscription` targets, mirroring `Event`'s
+/// variants plus the per-user scoping `OrderUpdate` needs since it's a
+/// private channel rather than a public symbol feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Trade,
+    Depth,
+    Ticker,
+    BookTicker,
+    Kline,
+    SymbolInfo,
+    OrderUpdate(UserId),
+}
+
+/// A `(channel, symbol)` interest a `Publisher` registers, like the
+/// subscribe/unsubscribe channel scheme streaming market-data clients use
+/// (e.g. `trade.SOL_USDC`, `depth.BTC/USD`, `ticker.*`). `channel: None` or
+/// `symbol: None` act as wildcards over that dimension;
+/// `Subscription::everything()` wildcards both, which is what a `Publisher`
+/// defaults to so pre-existing publishers keep receiving every event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    pub channel: Option<Channel>,
+    pub symbol: Option<String>,
+}
+
+impl Subscription {
+    /// Matches every channel and symbol.
+    pub fn everything() -> Self {
+        Self {
+            channel: None,
+            symbol: None,
+        }
+    }
+
+    /// Matches every symbol on `channel`, e.g. `ticker.*`.
+    pub fn channel(channel: Channel) -> Self {
+        Self {
+            channel: Some(channel),
+            symbol: None,
+        }
+    }
+
+    /// Matches one symbol on `channel`, e.g. `trade.SOL_USDC`. `symbol` of
+    /// `"*"` is equivalent to `Subscription::channel`.
+    pub fn channel_and_symbol(channel: Channel, symbol: impl Into<String>) -> Self {
+        Self {
+            channel: Some(channel),
+            symbol: Some(symbol.into()),
+        }
+    }
+
+    /// Whether `event` falls within this subscription's channel and symbol.
+    pub fn matches(&self, event: &Event) -> bool {
+        if let Some(channel) = &self.channel {
+            if !Self::channel_matches(channel, event) {
+                return false;
+            }
+        }
+
+        if let Some(symbol) = &self.symbol {
+            if symbol != "*" && event.symbol() != Some(symbol.as_str()) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn channel_matches(channel: &Channel, event: &Event) -> bool {
+        match (channel, event) {
+            (Channel::Trade, Event::Trade(_)) => true,
+            (Channel::Depth, Event::Depth(_)) => true,
+            (Channel::Ticker, Event::Ticker(_)) => true,
+            (Channel::BookTicker, Event::BookTicker(_)) => true,
+            (Channel::Kline, Event::Kline(_)) => true,
+            (Channel::SymbolInfo, Event::SymbolInfo(_)) => true,
+            (Channel::OrderUpdate(user_id), Event::OrderUpdate(update)) => {
+                update.user_id() == *user_id
+            }
+            _ => false,
+        }
+    }
+}
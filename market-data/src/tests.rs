@@ -3,13 +3,17 @@ use crate::{
     aggregator::Aggregator,
     pipeline::MarketDataPipeline,
     publisher::publisher::Publisher,
+    subscription::{Channel, Subscription},
     transformer::Transformer,
-    types::{DepthEvent, Event as WSEvent, TickerEvent, TradeEvent, UserOrderUpdateEvent},
+    types::{
+        is_sequence_gap, BookTickerEvent, DepthEvent, Event as WSEvent, FillUpdateStatus,
+        OpenOrder, OrderType, SymbolInfoEvent, TickerEvent, TradeEvent, UserOrderUpdateEvent,
+    },
 };
 use crossbeam_channel;
 use protocol::types::{
-    BookUpdate, CancelReason, Event, Fill, OrderAck, OrderCancelled, OrderReject, PriceLevel,
-    RejectReason, Side, Trade,
+    BookUpdate, CancelReason, Event, Fill, FillRole, OrderAck, OrderCancelled, OrderReject,
+    OrderStatus, OrderType as EngineOrderType, PriceLevel, RejectReason, Side, Trade,
 };
 use std::sync::{Arc, Mutex};
 
@@ -74,6 +78,8 @@ mod tests {
             symbol: "SOL_USDC".to_string(),
             quantity: 50,
             price: 50000,
+            maker_fee: 0,
+            taker_fee: 0,
             timestamp: 1234567890,
         };
         let event = Event::Trade(trade);
@@ -96,13 +102,18 @@ mod tests {
     fn test_transformer_fill_event() {
         let transformer = Transformer::new();
         let fill = Fill {
+            trade_id: 1,
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
             side: Side::Buy,
+            role: FillRole::Taker,
             filled_quantity: 25,
             filled_price: 50000,
             remaining_quantity: 25,
+            cumulative_quantity: 25,
+            avg_fill_price: 50000,
+            status: OrderStatus::PartiallyFilled,
         };
         let event = Event::Fill(fill);
 
@@ -136,6 +147,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            order_type: EngineOrderType::Limit,
         };
         let event = Event::OrderAck(ack);
 
@@ -266,21 +278,29 @@ mod tests {
                 symbol: "SOL_USDC".to_string(),
                 quantity: 50,
                 price: 50000,
+                maker_fee: 0,
+                taker_fee: 0,
                 timestamp: 1234567890,
             }),
             Event::Fill(Fill {
+                trade_id: 1,
                 order_id: 1,
                 user_id: 100,
                 symbol: "SOL_USDC".to_string(),
                 side: Side::Buy,
+                role: FillRole::Taker,
                 filled_quantity: 25,
                 filled_price: 50000,
                 remaining_quantity: 25,
+                cumulative_quantity: 25,
+                avg_fill_price: 50000,
+                status: OrderStatus::PartiallyFilled,
             }),
             Event::OrderAck(OrderAck {
                 order_id: 1,
                 user_id: 100,
                 symbol: "SOL_USDC".to_string(),
+                order_type: EngineOrderType::Limit,
             }),
         ];
 
@@ -306,6 +326,7 @@ mod tests {
             price: 50000,
             quantity: 10,
             timestamp: 1000,
+            seq: 0,
         });
 
         let result = aggregator.process(trade);
@@ -330,6 +351,7 @@ mod tests {
             price: 50000,
             quantity: 10,
             timestamp: 1000,
+            seq: 0,
         });
         let result1 = aggregator.process(trade1);
         let ticker1 = result1
@@ -358,6 +380,7 @@ mod tests {
             price: 51000,
             quantity: 20,
             timestamp: 2000,
+            seq: 0,
         });
         let result2 = aggregator.process(trade2);
         let ticker2 = result2
@@ -400,6 +423,7 @@ mod tests {
                 price,
                 quantity: qty,
                 timestamp: 1000,
+                seq: 0,
             });
             aggregator.process(trade);
         }
@@ -411,6 +435,7 @@ mod tests {
             price: 50500,
             quantity: 1,
             timestamp: 5000,
+            seq: 0,
         });
         let result = aggregator.process(final_trade);
         let ticker = result
@@ -443,6 +468,10 @@ mod tests {
             asks: vec![],
             timestamp: 1000,
             last_price: None,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
         });
 
         let depth2 = WSEvent::Depth(DepthEvent {
@@ -451,6 +480,10 @@ mod tests {
             asks: vec![],
             timestamp: 150, // Within 100ms throttle window
             last_price: None,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
         });
 
         // First depth should pass through
@@ -474,6 +507,10 @@ mod tests {
             asks: vec![],
             timestamp: 1000,
             last_price: None,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
         });
 
         // First depth
@@ -491,6 +528,10 @@ mod tests {
             asks: vec![],
             timestamp: 1200, // 200ms later (outside throttle window)
             last_price: None,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
         });
 
         // This should pass through if enough time has passed
@@ -500,262 +541,1465 @@ mod tests {
     }
 
     #[test]
-    fn test_aggregator_order_update_passes_through() {
+    fn test_aggregator_depth_diff_only_carries_changed_levels() {
         let mut aggregator = Aggregator::new();
+        let symbol = "SOL_USDC".to_string();
 
-        let order_update = WSEvent::OrderUpdate(UserOrderUpdateEvent::Ack {
-            order_id: 1,
-            user_id: 100,
-            symbol: "SOL_USDC".to_string(),
+        let depth1 = WSEvent::Depth(DepthEvent {
+            symbol: symbol.clone(),
+            bids: vec![
+                PriceLevel {
+                    price: 49900,
+                    quantity: 100,
+                },
+                PriceLevel {
+                    price: 49800,
+                    quantity: 50,
+                },
+            ],
+            asks: vec![PriceLevel {
+                price: 50100,
+                quantity: 25,
+            }],
+            last_price: Some(50000),
             timestamp: 1000,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
         });
 
-        let result = aggregator.process(order_update);
-        assert_eq!(result.len(), 1);
-        assert!(matches!(result[0], WSEvent::OrderUpdate(_)));
+        let result1 = aggregator.process(depth1);
+        match &result1[0] {
+            WSEvent::Depth(d) => {
+                // Nothing published yet, so the first diff is the full book.
+                assert_eq!(d.bids.len(), 2);
+                assert_eq!(d.asks.len(), 1);
+            }
+            _ => panic!("expected Depth event"),
+        }
+
+        // 49900 unchanged, 49800 removed, 49700 new.
+        let depth2 = WSEvent::Depth(DepthEvent {
+            symbol: symbol.clone(),
+            bids: vec![
+                PriceLevel {
+                    price: 49900,
+                    quantity: 100,
+                },
+                PriceLevel {
+                    price: 49700,
+                    quantity: 75,
+                },
+            ],
+            asks: vec![PriceLevel {
+                price: 50100,
+                quantity: 25,
+            }],
+            last_price: Some(50000),
+            timestamp: 1200,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
+        });
+
+        // The depth throttle runs on wall-clock time, not on the event's
+        // timestamp, so wait out its 100ms window.
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        let result2 = aggregator.process(depth2);
+        match &result2[0] {
+            WSEvent::Depth(d) => {
+                // Unchanged 49900 and unchanged ask are dropped; only the
+                // new level and the removal (quantity 0) remain.
+                assert_eq!(d.bids.len(), 2);
+                assert!(d
+                    .bids
+                    .iter()
+                    .any(|l| l.price == 49700 && l.quantity == 75));
+                assert!(d.bids.iter().any(|l| l.price == 49800 && l.quantity == 0));
+                assert!(d.asks.is_empty());
+            }
+            _ => panic!("expected Depth event"),
+        }
+    }
+
+    fn find_book_ticker(result: &[WSEvent]) -> Option<&BookTickerEvent> {
+        result.iter().find_map(|e| match e {
+            WSEvent::BookTicker(b) => Some(b),
+            _ => None,
+        })
     }
 
     #[test]
-    fn test_aggregator_multiple_symbols() {
+    fn test_aggregator_depth_emits_book_ticker_on_first_update() {
         let mut aggregator = Aggregator::new();
+        let symbol = "SOL_USDC".to_string();
 
-        // Trade for SOL_USDC
-        let trade1 = WSEvent::Trade(TradeEvent {
-            trade_id: 1,
-            symbol: "SOL_USDC".to_string(),
-            price: 50000,
-            quantity: 10,
+        let depth = WSEvent::Depth(DepthEvent {
+            symbol: symbol.clone(),
+            bids: vec![PriceLevel {
+                price: 49900,
+                quantity: 100,
+            }],
+            asks: vec![PriceLevel {
+                price: 50100,
+                quantity: 25,
+            }],
+            last_price: Some(50000),
             timestamp: 1000,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
         });
 
-        // Trade for BTC/USD
-        let trade2 = WSEvent::Trade(TradeEvent {
-            trade_id: 2,
-            symbol: "BTC/USD".to_string(),
-            price: 60000,
-            quantity: 5,
-            timestamp: 2000,
-        });
+        let result = aggregator.process(depth);
+        let ticker = find_book_ticker(&result).expect("expected a BookTicker event");
+        assert_eq!(ticker.best_bid, 49900);
+        assert_eq!(ticker.best_bid_qty, 100);
+        assert_eq!(ticker.best_ask, 50100);
+        assert_eq!(ticker.best_ask_qty, 25);
+    }
 
-        let result1 = aggregator.process(trade1);
-        let result2 = aggregator.process(trade2);
+    #[test]
+    fn test_aggregator_depth_does_not_emit_book_ticker_when_unchanged() {
+        let mut aggregator = Aggregator::new();
+        let symbol = "SOL_USDC".to_string();
 
-        // Both should produce tickers
-        assert!(result1.iter().any(|e| matches!(e, WSEvent::Ticker(_))));
-        assert!(result2.iter().any(|e| matches!(e, WSEvent::Ticker(_))));
+        let depth1 = WSEvent::Depth(DepthEvent {
+            symbol: symbol.clone(),
+            bids: vec![PriceLevel {
+                price: 49900,
+                quantity: 100,
+            }],
+            asks: vec![PriceLevel {
+                price: 50100,
+                quantity: 25,
+            }],
+            last_price: Some(50000),
+            timestamp: 1000,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
+        });
+        aggregator.process(depth1);
 
-        // Tickers should be for different symbols
-        let ticker1 = result1
-            .iter()
-            .find_map(|e| {
-                if let WSEvent::Ticker(t) = e {
-                    Some(t)
-                } else {
-                    None
-                }
-            })
-            .unwrap();
-        let ticker2 = result2
-            .iter()
-            .find_map(|e| {
-                if let WSEvent::Ticker(t) = e {
-                    Some(t)
-                } else {
-                    None
-                }
-            })
-            .unwrap();
+        // Same top-of-book, just a deeper level added; BBO hasn't moved.
+        let depth2 = WSEvent::Depth(DepthEvent {
+            symbol: symbol.clone(),
+            bids: vec![
+                PriceLevel {
+                    price: 49900,
+                    quantity: 100,
+                },
+                PriceLevel {
+                    price: 49800,
+                    quantity: 50,
+                },
+            ],
+            asks: vec![PriceLevel {
+                price: 50100,
+                quantity: 25,
+            }],
+            last_price: Some(50000),
+            timestamp: 1200,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
+        });
 
-        assert_eq!(ticker1.symbol, "SOL_USDC");
-        assert_eq!(ticker2.symbol, "BTC/USD");
-        assert_eq!(ticker1.last_price, 50000);
-        assert_eq!(ticker2.last_price, 60000);
+        let result2 = aggregator.process(depth2);
+        assert!(find_book_ticker(&result2).is_none());
     }
 
-    // ========== Pipeline Tests ==========
-
     #[test]
-    fn test_pipeline_processes_trade_event() {
-        let mock_pub = MockPublisher::new();
-        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
-        let mut pipeline = MarketDataPipeline::new(publishers);
+    fn test_aggregator_depth_emits_book_ticker_when_top_of_book_moves() {
+        let mut aggregator = Aggregator::new();
+        let symbol = "SOL_USDC".to_string();
 
-        let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
+        let depth1 = WSEvent::Depth(DepthEvent {
+            symbol: symbol.clone(),
+            bids: vec![PriceLevel {
+                price: 49900,
+                quantity: 100,
+            }],
+            asks: vec![PriceLevel {
+                price: 50100,
+                quantity: 25,
+            }],
+            last_price: Some(50000),
+            timestamp: 1000,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
+        });
+        aggregator.process(depth1);
 
-        // Spawn pipeline in background
-        let handle = std::thread::spawn(move || {
-            pipeline.run(event_rx);
+        let depth2 = WSEvent::Depth(DepthEvent {
+            symbol: symbol.clone(),
+            bids: vec![PriceLevel {
+                price: 49950,
+                quantity: 100,
+            }],
+            asks: vec![PriceLevel {
+                price: 50100,
+                quantity: 25,
+            }],
+            last_price: Some(50000),
+            timestamp: 1200,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
         });
 
-        // Send a trade event
-        let trade = Trade {
-            trade_id: 1,
-            maker_order_id: 10,
-            maker_user_id: 100,
-            taker_order_id: 20,
-            taker_user_id: 200,
-            symbol: "SOL_USDC".to_string(),
-            quantity: 50,
-            price: 50000,
-            timestamp: 1234567890,
-        };
-        event_tx.send(Event::Trade(trade)).unwrap();
+        let result2 = aggregator.process(depth2);
+        let ticker = find_book_ticker(&result2).expect("expected a BookTicker event");
+        assert_eq!(ticker.best_bid, 49950);
+    }
 
-        // Give pipeline time to process
-        std::thread::sleep(std::time::Duration::from_millis(200));
+    #[test]
+    fn test_aggregator_depth_emits_no_book_ticker_when_one_side_empty() {
+        let mut aggregator = Aggregator::new();
 
-        drop(event_tx);
-        handle.join().unwrap();
+        let depth = WSEvent::Depth(DepthEvent {
+            symbol: "SOL_USDC".to_string(),
+            bids: vec![PriceLevel {
+                price: 49900,
+                quantity: 100,
+            }],
+            asks: vec![],
+            last_price: Some(50000),
+            timestamp: 1000,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
+        });
 
-        // Check that events were published
-        let published = mock_pub.get_published();
-        assert!(!published.is_empty(), "Should have published events");
-        assert!(
-            published.iter().any(|e| matches!(e, WSEvent::Trade(_))),
-            "Should have published trade event"
-        );
+        let result = aggregator.process(depth);
+        assert!(find_book_ticker(&result).is_none());
     }
 
     #[test]
-    fn test_pipeline_processes_multiple_events() {
-        let mock_pub = MockPublisher::new();
-        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
-        let mut pipeline = MarketDataPipeline::new(publishers);
-
-        let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
+    fn test_aggregator_order_update_passes_through() {
+        let mut aggregator = Aggregator::new();
 
-        let handle = std::thread::spawn(move || {
-            pipeline.run(event_rx);
+        let order_update = WSEvent::OrderUpdate(UserOrderUpdateEvent::Ack {
+            order_id: 1,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            order_type: OrderType::Limit,
+            trigger_price: None,
+            timestamp: 1000,
+            seq: 0,
         });
 
-        // Send multiple events
-        for i in 1..=5 {
-            let trade = Trade {
-                trade_id: i,
-                maker_order_id: 10,
-                maker_user_id: 100,
-                taker_order_id: 20,
-                taker_user_id: 200,
-                symbol: "SOL_USDC".to_string(),
-                quantity: 50,
-                price: 50000 + i,
-                timestamp: 1234567890i64 + i as i64,
-            };
-            event_tx.send(Event::Trade(trade)).unwrap();
-        }
-
-        std::thread::sleep(std::time::Duration::from_millis(200));
+        // Ack, then the OpenOrders snapshot it opens.
+        let result = aggregator.process(order_update);
+        assert_eq!(result.len(), 2);
+        assert!(matches!(result[0], WSEvent::OrderUpdate(_)));
+        assert!(find_open_orders(&result).is_some());
+    }
 
-        drop(event_tx);
-        handle.join().unwrap();
+    fn find_status(result: &[WSEvent]) -> Option<&UserOrderUpdateEvent> {
+        result.iter().find_map(|e| match e {
+            WSEvent::OrderUpdate(status @ UserOrderUpdateEvent::Status { .. }) => Some(status),
+            _ => None,
+        })
+    }
 
-        let published = mock_pub.get_published();
-        assert!(
-            published.len() >= 5,
-            "Should have published multiple events"
-        );
+    fn find_open_orders(result: &[WSEvent]) -> Option<&Vec<OpenOrder>> {
+        result.iter().find_map(|e| match e {
+            WSEvent::OrderUpdate(UserOrderUpdateEvent::OpenOrders { orders, .. }) => Some(orders),
+            _ => None,
+        })
     }
 
     #[test]
-    fn test_pipeline_multiple_publishers() {
-        let mock_pub1 = MockPublisher::new();
-        let mock_pub2 = MockPublisher::new();
-        let publishers: Vec<Box<dyn Publisher>> =
-            vec![Box::new(mock_pub1.clone()), Box::new(mock_pub2.clone())];
-        let mut pipeline = MarketDataPipeline::new(publishers);
+    fn test_aggregator_open_orders_snapshot_tracks_lifecycle() {
+        let mut aggregator = Aggregator::new();
 
-        let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
+        let acked = aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Ack {
+            order_id: 1,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            order_type: OrderType::Limit,
+            trigger_price: None,
+            timestamp: 1000,
+            seq: 0,
+        }));
 
-        let handle = std::thread::spawn(move || {
-            pipeline.run(event_rx);
-        });
+        let open = find_open_orders(&acked).expect("Ack carries an OpenOrders snapshot");
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].order_id, 1);
+        assert_eq!(open[0].cumulative_filled, 0);
+        assert_eq!(open[0].average_fill_price, None);
+        assert_eq!(aggregator.open_orders_snapshot(100), open.clone());
 
-        let trade = Trade {
+        let partially_filled = aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 1,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
             trade_id: 1,
-            maker_order_id: 10,
-            maker_user_id: 100,
+            status: FillUpdateStatus::New,
+            filled_quantity: 4,
+            filled_price: 50000,
+            remaining_quantity: 6,
+            timestamp: 2000,
+            seq: 0,
+        }));
+
+        let open = find_open_orders(&partially_filled).expect("Fill carries an OpenOrders snapshot");
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].cumulative_filled, 4);
+        assert_eq!(open[0].average_fill_price, Some(50000));
+
+        let fully_filled = aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 1,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trade_id: 2,
+            status: FillUpdateStatus::New,
+            filled_quantity: 6,
+            filled_price: 51000,
+            remaining_quantity: 0,
+            timestamp: 3000,
+            seq: 0,
+        }));
+
+        // Fully filled: no longer open.
+        let open = find_open_orders(&fully_filled).expect("Fill carries an OpenOrders snapshot");
+        assert!(open.is_empty());
+        assert!(aggregator.open_orders_snapshot(100).is_empty());
+    }
+
+    #[test]
+    fn test_aggregator_cancelled_order_leaves_open_orders_snapshot() {
+        let mut aggregator = Aggregator::new();
+
+        aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Ack {
+            order_id: 2,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            order_type: OrderType::Limit,
+            trigger_price: None,
+            timestamp: 1000,
+            seq: 0,
+        }));
+
+        let cancelled = aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Cancelled {
+            order_id: 2,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            timestamp: 2000,
+            seq: 0,
+        }));
+
+        let open = find_open_orders(&cancelled).expect("Cancelled carries an OpenOrders snapshot");
+        assert!(open.is_empty());
+        assert!(aggregator.open_orders_snapshot(100).is_empty());
+    }
+
+    #[test]
+    fn test_aggregator_reject_does_not_emit_open_orders_snapshot() {
+        let mut aggregator = Aggregator::new();
+
+        let result = aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Reject {
+            order_id: 3,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            reason: "InvalidQuantity".to_string(),
+            message: "bad qty".to_string(),
+            timestamp: 1000,
+            seq: 0,
+        }));
+
+        assert!(find_open_orders(&result).is_none());
+    }
+
+    #[test]
+    fn test_aggregator_partial_then_final_fill_accumulates() {
+        let mut aggregator = Aggregator::new();
+
+        let result1 = aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 1,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trade_id: 1,
+            status: FillUpdateStatus::New,
+            filled_quantity: 4,
+            filled_price: 50000,
+            remaining_quantity: 6,
+            timestamp: 1000,
+            seq: 0,
+        }));
+
+        match find_status(&result1).unwrap() {
+            UserOrderUpdateEvent::Status {
+                cumulative_filled,
+                remaining,
+                average_fill_price,
+                state,
+                ..
+            } => {
+                assert_eq!(*cumulative_filled, 4);
+                assert_eq!(*remaining, 6);
+                assert_eq!(*average_fill_price, Some(50000));
+                assert_eq!(*state, protocol::types::OrderStatus::PartiallyFilled);
+            }
+            _ => panic!("expected Status"),
+        }
+
+        let result2 = aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 1,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trade_id: 2,
+            status: FillUpdateStatus::New,
+            filled_quantity: 6,
+            filled_price: 51000,
+            remaining_quantity: 0,
+            timestamp: 2000,
+            seq: 0,
+        }));
+
+        match find_status(&result2).unwrap() {
+            UserOrderUpdateEvent::Status {
+                cumulative_filled,
+                remaining,
+                average_fill_price,
+                state,
+                ..
+            } => {
+                assert_eq!(*cumulative_filled, 10);
+                assert_eq!(*remaining, 0);
+                // VWAP across (4 @ 50000) and (6 @ 51000).
+                assert_eq!(*average_fill_price, Some((4 * 50000 + 6 * 51000) / 10));
+                assert_eq!(*state, protocol::types::OrderStatus::Filled);
+            }
+            _ => panic!("expected Status"),
+        }
+    }
+
+    #[test]
+    fn test_aggregator_fully_filled_order_tracking_is_evicted() {
+        let mut aggregator = Aggregator::new();
+
+        aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 1,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trade_id: 1,
+            status: FillUpdateStatus::New,
+            filled_quantity: 10,
+            filled_price: 50000,
+            remaining_quantity: 0,
+            timestamp: 1000,
+            seq: 0,
+        }));
+
+        // A later, unrelated Fill reusing the same order_id starts fresh,
+        // proving the prior state was evicted rather than carried forward.
+        let result = aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 1,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trade_id: 2,
+            status: FillUpdateStatus::New,
+            filled_quantity: 3,
+            filled_price: 40000,
+            remaining_quantity: 7,
+            timestamp: 2000,
+            seq: 0,
+        }));
+
+        match find_status(&result).unwrap() {
+            UserOrderUpdateEvent::Status {
+                cumulative_filled, ..
+            } => assert_eq!(*cumulative_filled, 3),
+            _ => panic!("expected Status"),
+        }
+    }
+
+    #[test]
+    fn test_aggregator_cancelled_order_reports_cumulative_state() {
+        let mut aggregator = Aggregator::new();
+
+        aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 2,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trade_id: 1,
+            status: FillUpdateStatus::New,
+            filled_quantity: 4,
+            filled_price: 50000,
+            remaining_quantity: 6,
+            timestamp: 1000,
+            seq: 0,
+        }));
+
+        let result = aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Cancelled {
+            order_id: 2,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            timestamp: 2000,
+            seq: 0,
+        }));
+
+        match find_status(&result).unwrap() {
+            UserOrderUpdateEvent::Status {
+                cumulative_filled,
+                remaining,
+                average_fill_price,
+                state,
+                ..
+            } => {
+                assert_eq!(*cumulative_filled, 4);
+                assert_eq!(*remaining, 0);
+                assert_eq!(*average_fill_price, Some(50000));
+                assert_eq!(*state, protocol::types::OrderStatus::Cancelled);
+            }
+            _ => panic!("expected Status"),
+        }
+    }
+
+    #[test]
+    fn test_aggregator_rejected_order_reports_zero_state() {
+        let mut aggregator = Aggregator::new();
+
+        let result = aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Reject {
+            order_id: 3,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            reason: "InvalidQuantity".to_string(),
+            message: "bad qty".to_string(),
+            timestamp: 1000,
+            seq: 0,
+        }));
+
+        match find_status(&result).unwrap() {
+            UserOrderUpdateEvent::Status {
+                cumulative_filled,
+                remaining,
+                average_fill_price,
+                state,
+                ..
+            } => {
+                assert_eq!(*cumulative_filled, 0);
+                assert_eq!(*remaining, 0);
+                assert_eq!(*average_fill_price, None);
+                assert_eq!(*state, protocol::types::OrderStatus::Rejected);
+            }
+            _ => panic!("expected Status"),
+        }
+    }
+
+    #[test]
+    fn test_aggregator_revoke_fill_reverses_cumulative_state() {
+        let mut aggregator = Aggregator::new();
+
+        aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 5,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trade_id: 1,
+            status: FillUpdateStatus::New,
+            filled_quantity: 4,
+            filled_price: 50000,
+            remaining_quantity: 6,
+            timestamp: 1000,
+            seq: 0,
+        }));
+        aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 5,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trade_id: 2,
+            status: FillUpdateStatus::New,
+            filled_quantity: 3,
+            filled_price: 51000,
+            remaining_quantity: 3,
+            timestamp: 2000,
+            seq: 0,
+        }));
+
+        // Trade 2 gets reorg'd out; only trade 1's 4 @ 50000 should remain.
+        let events = aggregator.revoke_fill(5, 2).expect("fill is on record");
+        assert_eq!(events.len(), 2);
+
+        match &events[0] {
+            WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+                trade_id,
+                status,
+                filled_quantity,
+                filled_price,
+                remaining_quantity,
+                ..
+            }) => {
+                assert_eq!(*trade_id, 2);
+                assert_eq!(*status, FillUpdateStatus::Revoke);
+                assert_eq!(*filled_quantity, 3);
+                assert_eq!(*filled_price, 51000);
+                assert_eq!(*remaining_quantity, 3);
+            }
+            _ => panic!("expected a revoking Fill"),
+        }
+
+        match &events[1] {
+            WSEvent::OrderUpdate(UserOrderUpdateEvent::Status {
+                cumulative_filled,
+                remaining,
+                average_fill_price,
+                state,
+                ..
+            }) => {
+                assert_eq!(*cumulative_filled, 4);
+                assert_eq!(*remaining, 6);
+                assert_eq!(*average_fill_price, Some(50000));
+                assert_eq!(*state, protocol::types::OrderStatus::PartiallyFilled);
+            }
+            _ => panic!("expected a Status"),
+        }
+    }
+
+    #[test]
+    fn test_aggregator_revoke_fill_unknown_trade_id_returns_none() {
+        let mut aggregator = Aggregator::new();
+
+        aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 6,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trade_id: 1,
+            status: FillUpdateStatus::New,
+            filled_quantity: 4,
+            filled_price: 50000,
+            remaining_quantity: 6,
+            timestamp: 1000,
+            seq: 0,
+        }));
+
+        assert!(aggregator.revoke_fill(6, 999).is_none());
+        // An order that was never filled at all has no tracking state either.
+        assert!(aggregator.revoke_fill(12345, 1).is_none());
+    }
+
+    #[test]
+    fn test_aggregator_revoke_fill_after_full_fill_is_unrecoverable() {
+        let mut aggregator = Aggregator::new();
+
+        aggregator.process(WSEvent::OrderUpdate(UserOrderUpdateEvent::Fill {
+            order_id: 7,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trade_id: 1,
+            status: FillUpdateStatus::New,
+            filled_quantity: 10,
+            filled_price: 50000,
+            remaining_quantity: 0,
+            timestamp: 1000,
+            seq: 0,
+        }));
+
+        // Tracking was evicted on full fill, so the revoke can't be applied.
+        assert!(aggregator.revoke_fill(7, 1).is_none());
+    }
+
+    #[test]
+    fn test_aggregator_multiple_symbols() {
+        let mut aggregator = Aggregator::new();
+
+        // Trade for SOL_USDC
+        let trade1 = WSEvent::Trade(TradeEvent {
+            trade_id: 1,
+            symbol: "SOL_USDC".to_string(),
+            price: 50000,
+            quantity: 10,
+            timestamp: 1000,
+            seq: 0,
+        });
+
+        // Trade for BTC/USD
+        let trade2 = WSEvent::Trade(TradeEvent {
+            trade_id: 2,
+            symbol: "BTC/USD".to_string(),
+            price: 60000,
+            quantity: 5,
+            timestamp: 2000,
+            seq: 0,
+        });
+
+        let result1 = aggregator.process(trade1);
+        let result2 = aggregator.process(trade2);
+
+        // Both should produce tickers
+        assert!(result1.iter().any(|e| matches!(e, WSEvent::Ticker(_))));
+        assert!(result2.iter().any(|e| matches!(e, WSEvent::Ticker(_))));
+
+        // Tickers should be for different symbols
+        let ticker1 = result1
+            .iter()
+            .find_map(|e| {
+                if let WSEvent::Ticker(t) = e {
+                    Some(t)
+                } else {
+                    None
+                }
+            })
+            .unwrap();
+        let ticker2 = result2
+            .iter()
+            .find_map(|e| {
+                if let WSEvent::Ticker(t) = e {
+                    Some(t)
+                } else {
+                    None
+                }
+            })
+            .unwrap();
+
+        assert_eq!(ticker1.symbol, "SOL_USDC");
+        assert_eq!(ticker2.symbol, "BTC/USD");
+        assert_eq!(ticker1.last_price, 50000);
+        assert_eq!(ticker2.last_price, 60000);
+    }
+
+    // ========== Kline Aggregation Tests ==========
+
+    #[test]
+    fn test_aggregator_kline_one_event_per_interval() {
+        use crate::types::KlineInterval;
+
+        let mut aggregator = Aggregator::new();
+        let trade = WSEvent::Trade(TradeEvent {
+            trade_id: 1,
+            symbol: "SOL_USDC".to_string(),
+            price: 50000,
+            quantity: 10,
+            timestamp: 1_000,
+            seq: 0,
+        });
+
+        let result = aggregator.process(trade);
+        let klines: Vec<_> = result
+            .iter()
+            .filter_map(|e| if let WSEvent::Kline(k) = e { Some(k) } else { None })
+            .collect();
+
+        // One opening candle per configured interval (1m, 5m, 1h, 1d).
+        assert_eq!(klines.len(), 4);
+        assert!(klines.iter().all(|k| !k.closed));
+        assert!(klines.iter().all(|k| k.open == 50000 && k.close == 50000));
+        assert!(klines
+            .iter()
+            .any(|k| k.interval == KlineInterval::OneMinute));
+        assert!(klines.iter().any(|k| k.interval == KlineInterval::OneDay));
+        assert!(klines
+            .iter()
+            .all(|k| k.close_time == k.open_time + k.interval.as_millis() - 1));
+    }
+
+    #[test]
+    fn test_aggregator_kline_updates_within_bucket() {
+        use crate::types::KlineInterval;
+
+        let mut aggregator = Aggregator::new();
+        let symbol = "SOL_USDC".to_string();
+
+        aggregator.process(WSEvent::Trade(TradeEvent {
+            trade_id: 1,
+            symbol: symbol.clone(),
+            price: 50000,
+            quantity: 10,
+            timestamp: 1_000,
+            seq: 0,
+        }));
+
+        // Still inside the same 1-minute bucket (60_000ms).
+        let result = aggregator.process(WSEvent::Trade(TradeEvent {
+            trade_id: 2,
+            symbol: symbol.clone(),
+            price: 51000,
+            quantity: 5,
+            timestamp: 2_000,
+            seq: 0,
+        }));
+
+        let one_min = result
+            .iter()
+            .find_map(|e| match e {
+                WSEvent::Kline(k) if k.interval == KlineInterval::OneMinute => Some(k),
+                _ => None,
+            })
+            .unwrap();
+
+        assert!(!one_min.closed);
+        assert_eq!(one_min.open, 50000);
+        assert_eq!(one_min.high, 51000);
+        assert_eq!(one_min.low, 50000);
+        assert_eq!(one_min.close, 51000);
+        assert_eq!(one_min.volume, 15);
+        assert_eq!(one_min.trade_count, 2);
+    }
+
+    #[test]
+    fn test_aggregator_kline_closes_on_bucket_rollover() {
+        use crate::types::KlineInterval;
+
+        let mut aggregator = Aggregator::new();
+        let symbol = "SOL_USDC".to_string();
+
+        aggregator.process(WSEvent::Trade(TradeEvent {
+            trade_id: 1,
+            symbol: symbol.clone(),
+            price: 50000,
+            quantity: 10,
+            timestamp: 1_000,
+            seq: 0,
+        }));
+
+        // 70s later: past the 1-minute bucket boundary.
+        let result = aggregator.process(WSEvent::Trade(TradeEvent {
+            trade_id: 2,
+            symbol: symbol.clone(),
+            price: 52000,
+            quantity: 5,
+            timestamp: 70_000,
+            seq: 0,
+        }));
+
+        let one_min_klines: Vec<_> = result
+            .iter()
+            .filter_map(|e| match e {
+                WSEvent::Kline(k) if k.interval == KlineInterval::OneMinute => Some(k),
+                _ => None,
+            })
+            .collect();
+
+        // The finished bucket closes, then a fresh one opens.
+        assert_eq!(one_min_klines.len(), 2);
+        assert!(one_min_klines[0].closed);
+        assert_eq!(one_min_klines[0].close, 50000);
+        assert!(!one_min_klines[1].closed);
+        assert_eq!(one_min_klines[1].open, 52000);
+        assert_eq!(one_min_klines[1].trade_count, 1);
+    }
+
+    // ========== Rolling-Window Ticker Tests ==========
+
+    #[test]
+    fn test_aggregator_ticker_window_evicts_stale_trades() {
+        let mut aggregator = Aggregator::with_ticker_window_ms(1_000);
+        let symbol = "SOL_USDC".to_string();
+
+        aggregator.process(WSEvent::Trade(TradeEvent {
+            trade_id: 1,
+            symbol: symbol.clone(),
+            price: 50000,
+            quantity: 10,
+            timestamp: 0,
+            seq: 0,
+        }));
+
+        // Well outside the 1s window: the first trade should be evicted and
+        // no longer influence `open`/`high`/`low`/`volume`.
+        let result = aggregator.process(WSEvent::Trade(TradeEvent {
+            trade_id: 2,
+            symbol: symbol.clone(),
+            price: 40000,
+            quantity: 3,
+            timestamp: 5_000,
+            seq: 0,
+        }));
+
+        let ticker = result
+            .iter()
+            .find_map(|e| if let WSEvent::Ticker(t) = e { Some(t) } else { None })
+            .unwrap();
+
+        assert_eq!(ticker.open, 40000);
+        assert_eq!(ticker.high, 40000);
+        assert_eq!(ticker.low, 40000);
+        assert_eq!(ticker.volume, 3);
+        assert_eq!(ticker.price_change, 0);
+    }
+
+    #[test]
+    fn test_aggregator_ticker_window_retains_recent_trades() {
+        let mut aggregator = Aggregator::with_ticker_window_ms(1_000);
+        let symbol = "SOL_USDC".to_string();
+
+        aggregator.process(WSEvent::Trade(TradeEvent {
+            trade_id: 1,
+            symbol: symbol.clone(),
+            price: 50000,
+            quantity: 10,
+            timestamp: 0,
+            seq: 0,
+        }));
+
+        // Still inside the 1s window.
+        let result = aggregator.process(WSEvent::Trade(TradeEvent {
+            trade_id: 2,
+            symbol: symbol.clone(),
+            price: 51000,
+            quantity: 5,
+            timestamp: 500,
+            seq: 0,
+        }));
+
+        let ticker = result
+            .iter()
+            .find_map(|e| if let WSEvent::Ticker(t) = e { Some(t) } else { None })
+            .unwrap();
+
+        assert_eq!(ticker.open, 50000);
+        assert_eq!(ticker.last_price, 51000);
+        assert_eq!(ticker.high, 51000);
+        assert_eq!(ticker.low, 50000);
+        assert_eq!(ticker.volume, 15);
+    }
+
+    // ========== Pipeline Tests ==========
+
+    #[test]
+    fn test_pipeline_processes_trade_event() {
+        let mock_pub = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
+        let mut pipeline = MarketDataPipeline::new(publishers);
+
+        let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
+
+        // Spawn pipeline in background
+        let handle = std::thread::spawn(move || {
+            pipeline.run(event_rx);
+        });
+
+        // Send a trade event
+        let trade = Trade {
+            trade_id: 1,
+            maker_order_id: 10,
+            maker_user_id: 100,
+            taker_order_id: 20,
+            taker_user_id: 200,
+            symbol: "SOL_USDC".to_string(),
+            quantity: 50,
+            price: 50000,
+            maker_fee: 0,
+            taker_fee: 0,
+            timestamp: 1234567890,
+        };
+        event_tx.send(Event::Trade(trade)).unwrap();
+
+        // Give pipeline time to process
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        drop(event_tx);
+        handle.join().unwrap();
+
+        // Check that events were published
+        let published = mock_pub.get_published();
+        assert!(!published.is_empty(), "Should have published events");
+        assert!(
+            published.iter().any(|e| matches!(e, WSEvent::Trade(_))),
+            "Should have published trade event"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_processes_multiple_events() {
+        let mock_pub = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
+        let mut pipeline = MarketDataPipeline::new(publishers);
+
+        let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
+
+        let handle = std::thread::spawn(move || {
+            pipeline.run(event_rx);
+        });
+
+        // Send multiple events
+        for i in 1..=5 {
+            let trade = Trade {
+                trade_id: i,
+                maker_order_id: 10,
+                maker_user_id: 100,
+                taker_order_id: 20,
+                taker_user_id: 200,
+                symbol: "SOL_USDC".to_string(),
+                quantity: 50,
+                price: 50000 + i,
+                maker_fee: 0,
+                taker_fee: 0,
+                timestamp: 1234567890i64 + i as i64,
+            };
+            event_tx.send(Event::Trade(trade)).unwrap();
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        drop(event_tx);
+        handle.join().unwrap();
+
+        let published = mock_pub.get_published();
+        assert!(
+            published.len() >= 5,
+            "Should have published multiple events"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_multiple_publishers() {
+        let mock_pub1 = MockPublisher::new();
+        let mock_pub2 = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> =
+            vec![Box::new(mock_pub1.clone()), Box::new(mock_pub2.clone())];
+        let mut pipeline = MarketDataPipeline::new(publishers);
+
+        let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
+
+        let handle = std::thread::spawn(move || {
+            pipeline.run(event_rx);
+        });
+
+        let trade = Trade {
+            trade_id: 1,
+            maker_order_id: 10,
+            maker_user_id: 100,
             taker_order_id: 20,
             taker_user_id: 200,
             symbol: "SOL_USDC".to_string(),
             quantity: 50,
             price: 50000,
+            maker_fee: 0,
+            taker_fee: 0,
             timestamp: 1234567890,
         };
         event_tx.send(Event::Trade(trade)).unwrap();
 
-        std::thread::sleep(std::time::Duration::from_millis(200));
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        drop(event_tx);
+        handle.join().unwrap();
+
+        // Both publishers should have received events
+        assert!(mock_pub1.count() > 0, "Publisher 1 should have events");
+        assert!(mock_pub2.count() > 0, "Publisher 2 should have events");
+    }
+
+    #[test]
+    fn test_pipeline_handles_all_event_types() {
+        let mock_pub = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
+        let mut pipeline = MarketDataPipeline::new(publishers);
+
+        let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
+
+        let handle = std::thread::spawn(move || {
+            pipeline.run(event_rx);
+        });
+
+        // Send all event types
+        event_tx
+            .send(Event::Trade(Trade {
+                trade_id: 1,
+                maker_order_id: 10,
+                maker_user_id: 100,
+                taker_order_id: 20,
+                taker_user_id: 200,
+                symbol: "SOL_USDC".to_string(),
+                quantity: 50,
+                price: 50000,
+                maker_fee: 0,
+                taker_fee: 0,
+                timestamp: 1234567890,
+            }))
+            .unwrap();
+
+        event_tx
+            .send(Event::Fill(Fill {
+                trade_id: 1,
+                order_id: 1,
+                user_id: 100,
+                symbol: "SOL_USDC".to_string(),
+                side: Side::Buy,
+                role: FillRole::Taker,
+                filled_quantity: 25,
+                filled_price: 50000,
+                remaining_quantity: 25,
+                cumulative_quantity: 25,
+                avg_fill_price: 50000,
+                status: OrderStatus::PartiallyFilled,
+            }))
+            .unwrap();
+
+        event_tx
+            .send(Event::OrderAck(OrderAck {
+                order_id: 1,
+                user_id: 100,
+                symbol: "SOL_USDC".to_string(),
+                order_type: EngineOrderType::Limit,
+            }))
+            .unwrap();
+
+        event_tx
+            .send(Event::BookUpdate(BookUpdate {
+                symbol: "SOL_USDC".to_string(),
+                bids: vec![],
+                asks: vec![],
+                last_price: Some(50000),
+            }))
+            .unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        drop(event_tx);
+        handle.join().unwrap();
+
+        let published = mock_pub.get_published();
+        assert!(!published.is_empty(), "Should have published events");
+    }
+
+    // ========== Backpressure / Conflation Tests ==========
+
+    #[test]
+    fn test_pipeline_conflates_depth_for_same_symbol() {
+        let mock_pub = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
+        let mut pipeline = MarketDataPipeline::with_capacity(publishers, 4, 60_000);
+
+        for i in 0..2000u64 {
+            pipeline.enqueue(WSEvent::Depth(DepthEvent {
+                symbol: "SOL_USDC".to_string(),
+                bids: vec![PriceLevel {
+                    price: 50000 + i,
+                    quantity: 1,
+                }],
+                asks: vec![],
+                last_price: None,
+                timestamp: 1000,
+                seq: 0,
+                first_seq: 0,
+                last_seq: 0,
+                is_snapshot: false,
+            }));
+        }
+
+        pipeline.enqueue(WSEvent::Trade(TradeEvent {
+            trade_id: 1,
+            symbol: "SOL_USDC".to_string(),
+            price: 50000,
+            quantity: 1,
+            timestamp: 1000,
+            seq: 0,
+        }));
+
+        pipeline.drain();
+
+        let published = mock_pub.get_published();
+        let depth_events: Vec<_> = published
+            .iter()
+            .filter_map(|e| if let WSEvent::Depth(d) = e { Some(d) } else { None })
+            .collect();
+
+        assert_eq!(
+            depth_events.len(),
+            1,
+            "only the freshest conflated depth update should survive"
+        );
+        assert_eq!(depth_events[0].bids[0].price, 50000 + 1999);
+        assert!(
+            published.iter().any(|e| matches!(e, WSEvent::Trade(_))),
+            "trades always pass through"
+        );
+
+        let stats = pipeline.stats();
+        assert_eq!(stats.conflated, 1999);
+        assert_eq!(stats.published, 2);
+    }
+
+    #[test]
+    fn test_pipeline_capacity_evicts_droppable_events_when_full() {
+        let mock_pub = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
+        let mut pipeline = MarketDataPipeline::with_capacity(publishers, 2, 60_000);
+
+        for i in 0..5u64 {
+            pipeline.enqueue(WSEvent::Depth(DepthEvent {
+                symbol: format!("SYM{i}"),
+                bids: vec![],
+                asks: vec![],
+                last_price: None,
+                timestamp: 1000,
+                seq: 0,
+                first_seq: 0,
+                last_seq: 0,
+                is_snapshot: false,
+            }));
+        }
+
+        pipeline.drain();
+
+        let stats = pipeline.stats();
+        assert!(
+            stats.dropped > 0,
+            "depth updates for distinct symbols beyond capacity should be evicted"
+        );
+        assert!(stats.published <= 2, "capacity bounds what survives to publish");
+    }
+
+    #[test]
+    fn test_pipeline_trades_never_dropped_even_over_capacity() {
+        let mock_pub = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
+        let mut pipeline = MarketDataPipeline::with_capacity(publishers, 1, 60_000);
+
+        for i in 0..50u64 {
+            pipeline.enqueue(WSEvent::Trade(TradeEvent {
+                trade_id: i,
+                symbol: "SOL_USDC".to_string(),
+                price: 50000,
+                quantity: 1,
+                timestamp: 1000,
+                seq: 0,
+            }));
+        }
+
+        pipeline.drain();
+
+        let published = mock_pub.get_published();
+        let trade_count = published
+            .iter()
+            .filter(|e| matches!(e, WSEvent::Trade(_)))
+            .count();
+
+        assert_eq!(trade_count, 50, "trades must never be dropped");
+        assert_eq!(pipeline.stats().dropped, 0);
+    }
+
+    #[test]
+    fn test_pipeline_drops_stale_conflatable_events() {
+        let mock_pub = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
+        let mut pipeline = MarketDataPipeline::with_capacity(publishers, 100, 0);
+
+        pipeline.enqueue(WSEvent::Ticker(TickerEvent {
+            symbol: "SOL_USDC".to_string(),
+            last_price: 50000,
+            open: 50000,
+            high: 50000,
+            low: 50000,
+            volume: 1,
+            price_change: 0,
+            price_change_percent: 0.0,
+            timestamp: 1000,
+            seq: 0,
+        }));
 
-        drop(event_tx);
-        handle.join().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        pipeline.drain();
 
-        // Both publishers should have received events
-        assert!(mock_pub1.count() > 0, "Publisher 1 should have events");
-        assert!(mock_pub2.count() > 0, "Publisher 2 should have events");
+        assert_eq!(
+            mock_pub.get_published().len(),
+            0,
+            "the ticker should have exceeded the 0ms staleness deadline"
+        );
+        assert_eq!(pipeline.stats().dropped, 1);
     }
 
+    // ========== Sequence Numbering Tests ==========
+
     #[test]
-    fn test_pipeline_handles_all_event_types() {
+    fn test_pipeline_stamps_contiguous_seq_per_symbol() {
         let mock_pub = MockPublisher::new();
         let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
-        let mut pipeline = MarketDataPipeline::new(publishers);
-
-        let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
-
-        let handle = std::thread::spawn(move || {
-            pipeline.run(event_rx);
-        });
+        let mut pipeline = MarketDataPipeline::with_capacity(publishers, 100, 60_000);
 
-        // Send all event types
-        event_tx
-            .send(Event::Trade(Trade {
-                trade_id: 1,
-                maker_order_id: 10,
-                maker_user_id: 100,
-                taker_order_id: 20,
-                taker_user_id: 200,
+        for i in 0..3u64 {
+            pipeline.enqueue(WSEvent::Trade(TradeEvent {
+                trade_id: i,
                 symbol: "SOL_USDC".to_string(),
-                quantity: 50,
                 price: 50000,
-                timestamp: 1234567890,
-            }))
-            .unwrap();
+                quantity: 1,
+                timestamp: 1000,
+                seq: 0,
+            }));
+        }
+        pipeline.drain();
 
-        event_tx
-            .send(Event::Fill(Fill {
-                order_id: 1,
-                user_id: 100,
-                symbol: "SOL_USDC".to_string(),
-                side: Side::Buy,
-                filled_quantity: 25,
-                filled_price: 50000,
-                remaining_quantity: 25,
-            }))
-            .unwrap();
+        let published = mock_pub.get_published();
+        let seqs: Vec<u64> = published
+            .iter()
+            .map(|e| match e {
+                WSEvent::Trade(t) => t.seq,
+                _ => panic!("expected Trade"),
+            })
+            .collect();
 
-        event_tx
-            .send(Event::OrderAck(OrderAck {
-                order_id: 1,
-                user_id: 100,
+        assert_eq!(seqs, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_pipeline_seq_is_independent_per_symbol() {
+        let mock_pub = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
+        let mut pipeline = MarketDataPipeline::with_capacity(publishers, 100, 60_000);
+
+        pipeline.enqueue(WSEvent::Trade(TradeEvent {
+            trade_id: 1,
+            symbol: "SOL_USDC".to_string(),
+            price: 50000,
+            quantity: 1,
+            timestamp: 1000,
+            seq: 0,
+        }));
+        pipeline.enqueue(WSEvent::Trade(TradeEvent {
+            trade_id: 2,
+            symbol: "BTC_USDC".to_string(),
+            price: 60000,
+            quantity: 1,
+            timestamp: 1000,
+            seq: 0,
+        }));
+        pipeline.drain();
+
+        let published = mock_pub.get_published();
+        for e in &published {
+            match e {
+                WSEvent::Trade(t) => assert_eq!(t.seq, 1, "each symbol starts its own sequence"),
+                _ => panic!("expected Trade"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_pipeline_conflated_depth_advances_seq_range_contiguously() {
+        let mock_pub = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
+        let mut pipeline = MarketDataPipeline::with_capacity(publishers, 100, 60_000);
+
+        // Three raw depth updates arrive before the first drain, so the
+        // first two are conflated away; the survivor should still report
+        // the full 1..=3 range it subsumes.
+        for i in 0..3u64 {
+            pipeline.enqueue(WSEvent::Depth(DepthEvent {
                 symbol: "SOL_USDC".to_string(),
-            }))
-            .unwrap();
+                bids: vec![PriceLevel {
+                    price: 50000 + i,
+                    quantity: 1,
+                }],
+                asks: vec![],
+                last_price: None,
+                timestamp: 1000,
+                seq: 0,
+                first_seq: 0,
+                last_seq: 0,
+                is_snapshot: false,
+            }));
+        }
+        pipeline.drain();
 
-        event_tx
-            .send(Event::BookUpdate(BookUpdate {
+        // A second batch, throttled down to one survivor again, should
+        // continue the range right where the first batch's left off.
+        for i in 3..5u64 {
+            pipeline.enqueue(WSEvent::Depth(DepthEvent {
                 symbol: "SOL_USDC".to_string(),
-                bids: vec![],
+                bids: vec![PriceLevel {
+                    price: 50000 + i,
+                    quantity: 1,
+                }],
                 asks: vec![],
-                last_price: Some(50000),
-            }))
-            .unwrap();
+                last_price: None,
+                timestamp: 1000,
+                seq: 0,
+                first_seq: 0,
+                last_seq: 0,
+                is_snapshot: false,
+            }));
+        }
+        pipeline.drain();
 
-        std::thread::sleep(std::time::Duration::from_millis(200));
+        let published = mock_pub.get_published();
+        let depth_events: Vec<_> = published
+            .iter()
+            .filter_map(|e| if let WSEvent::Depth(d) = e { Some(d) } else { None })
+            .collect();
+
+        assert_eq!(depth_events.len(), 2);
+        assert_eq!(depth_events[0].first_seq, 1);
+        assert_eq!(depth_events[0].last_seq, 3);
+        assert_eq!(depth_events[0].seq, 3);
+
+        // No gap between the two published ranges, even though every raw
+        // update in between was conflated away rather than published.
+        assert!(!is_sequence_gap(
+            depth_events[0].last_seq,
+            depth_events[1].first_seq
+        ));
+        assert_eq!(depth_events[1].first_seq, 4);
+        assert_eq!(depth_events[1].last_seq, 5);
+    }
 
-        drop(event_tx);
-        handle.join().unwrap();
+    #[test]
+    fn test_is_sequence_gap_detects_missed_updates() {
+        assert!(!is_sequence_gap(1, 2));
+        assert!(is_sequence_gap(1, 3));
+        assert!(is_sequence_gap(5, 5));
+    }
 
-        let published = mock_pub.get_published();
-        assert!(!published.is_empty(), "Should have published events");
+    #[test]
+    fn test_pipeline_depth_snapshot_covers_seq_seen_so_far() {
+        let mock_pub = MockPublisher::new();
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(mock_pub.clone())];
+        let mut pipeline = MarketDataPipeline::with_capacity(publishers, 100, 60_000);
+
+        assert!(
+            pipeline.depth_snapshot("SOL_USDC").is_none(),
+            "no snapshot exists before any depth update has been processed"
+        );
+
+        // The snapshot's book comes from the aggregator, so the updates go
+        // through `run`; its depth throttle runs on wall-clock time, hence
+        // the wait between them.
+        for i in 0..2u64 {
+            if i > 0 {
+                std::thread::sleep(std::time::Duration::from_millis(150));
+            }
+            let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
+            event_tx
+                .send(Event::BookUpdate(BookUpdate {
+                    symbol: "SOL_USDC".to_string(),
+                    bids: vec![PriceLevel {
+                        price: 50000 + i,
+                        quantity: 1,
+                    }],
+                    asks: vec![],
+                    last_price: None,
+                }))
+                .unwrap();
+            drop(event_tx);
+            pipeline.run(event_rx);
+        }
+        pipeline.drain();
+
+        let snapshot = pipeline
+            .depth_snapshot("SOL_USDC")
+            .expect("a depth update has been processed for this symbol");
+
+        match snapshot {
+            WSEvent::Depth(d) => {
+                assert!(d.is_snapshot);
+                assert_eq!(d.first_seq, 1);
+                assert_eq!(d.last_seq, 2);
+                assert_eq!(d.seq, 2);
+            }
+            _ => panic!("expected Depth"),
+        }
     }
 
     // ========== Types Tests ==========
@@ -768,6 +2012,7 @@ mod tests {
             price: 50000,
             quantity: 10,
             timestamp: 1000,
+            seq: 0,
         });
         assert!(trade.is_public(), "Trade should be public");
 
@@ -777,6 +2022,10 @@ mod tests {
             asks: vec![],
             timestamp: 1000,
             last_price: None,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
         });
         assert!(depth.is_public(), "Depth should be public");
 
@@ -790,6 +2039,7 @@ mod tests {
             price_change: 0,
             price_change_percent: 0.0,
             timestamp: 1000,
+            seq: 0,
         });
         assert!(ticker.is_public(), "Ticker should be public");
 
@@ -797,7 +2047,10 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            order_type: OrderType::Limit,
+            trigger_price: None,
             timestamp: 1000,
+            seq: 0,
         });
         assert!(!order_update.is_public(), "OrderUpdate should be private");
     }
@@ -810,6 +2063,7 @@ mod tests {
             price: 50000,
             quantity: 10,
             timestamp: 1000,
+            seq: 0,
         });
         assert_eq!(trade.user_id(), None, "Trade should not have user_id");
 
@@ -817,10 +2071,13 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            trade_id: 1,
+            status: FillUpdateStatus::New,
             filled_quantity: 10,
             filled_price: 50000,
             remaining_quantity: 0,
             timestamp: 1000,
+            seq: 0,
         });
         assert_eq!(fill.user_id(), Some(100), "Fill should have user_id");
 
@@ -828,7 +2085,10 @@ mod tests {
             order_id: 1,
             user_id: 200,
             symbol: "SOL_USDC".to_string(),
+            order_type: OrderType::Limit,
+            trigger_price: None,
             timestamp: 1000,
+            seq: 0,
         });
         assert_eq!(ack.user_id(), Some(200), "Ack should have user_id");
 
@@ -839,6 +2099,7 @@ mod tests {
             reason: "Invalid".to_string(),
             message: "Test".to_string(),
             timestamp: 1000,
+            seq: 0,
         });
         assert_eq!(reject.user_id(), Some(300), "Reject should have user_id");
 
@@ -847,6 +2108,7 @@ mod tests {
             user_id: 400,
             symbol: "SOL_USDC".to_string(),
             timestamp: 1000,
+            seq: 0,
         });
         assert_eq!(
             cancelled.user_id(),
@@ -863,6 +2125,7 @@ mod tests {
             price: 50000,
             quantity: 10,
             timestamp: 1000,
+            seq: 0,
         };
 
         let json = serde_json::to_string(&trade).unwrap();
@@ -889,6 +2152,10 @@ mod tests {
             }],
             timestamp: 1000,
             last_price: Some(50000),
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
         };
 
         let json = serde_json::to_string(&depth).unwrap();
@@ -912,6 +2179,7 @@ mod tests {
             price_change: 0,
             price_change_percent: 0.0,
             timestamp: 1000,
+            seq: 0,
         };
 
         let json = serde_json::to_string(&ticker).unwrap();
@@ -925,16 +2193,56 @@ mod tests {
         assert_eq!(ticker.volume, deserialized.volume);
     }
 
+    #[test]
+    fn test_symbol_info_event_serialization_and_price_scaling() {
+        use crate::types::SymbolFilter;
+
+        let info = SymbolInfoEvent {
+            symbol: "SOL_USDC".to_string(),
+            base_asset: "SOL".to_string(),
+            quote_asset: "USDC".to_string(),
+            base_precision: 4,
+            quote_precision: 2,
+            filters: vec![
+                SymbolFilter::PriceFilter {
+                    min_price: 1,
+                    max_price: 1_000_000_00,
+                    tick_size: 1,
+                },
+                SymbolFilter::LotSize {
+                    min_qty: 1,
+                    max_qty: 1_000_000,
+                    step_size: 1,
+                },
+                SymbolFilter::MinNotional { min_notional: 500 },
+            ],
+        };
+
+        assert_eq!(info.scale_price(5_012_345), 50123.45);
+
+        let json = serde_json::to_string(&info).unwrap();
+        let deserialized: SymbolInfoEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(info, deserialized);
+
+        let event = WSEvent::SymbolInfo(info);
+        assert!(event.is_public());
+        assert_eq!(event.user_id(), None);
+        assert_eq!(event.symbol(), Some("SOL_USDC"));
+    }
+
     #[test]
     fn test_user_order_update_event_serialization() {
         let fill = UserOrderUpdateEvent::Fill {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            trade_id: 1,
+            status: FillUpdateStatus::New,
             filled_quantity: 10,
             filled_price: 50000,
             remaining_quantity: 0,
             timestamp: 1000,
+            seq: 0,
         };
 
         let json = serde_json::to_string(&fill).unwrap();
@@ -962,4 +2270,256 @@ mod tests {
             _ => panic!("Fill events don't match"),
         }
     }
+
+    #[test]
+    fn test_ack_carries_order_type_and_trigger_price() {
+        let ack = UserOrderUpdateEvent::Ack {
+            order_id: 1,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            order_type: OrderType::TrailingStopPercent { trail: 250 },
+            trigger_price: Some(49000),
+            timestamp: 1000,
+            seq: 0,
+        };
+
+        let json = serde_json::to_string(&ack).unwrap();
+        let deserialized: UserOrderUpdateEvent = serde_json::from_str(&json).unwrap();
+
+        match deserialized {
+            UserOrderUpdateEvent::Ack {
+                order_type,
+                trigger_price,
+                ..
+            } => {
+                assert_eq!(order_type, OrderType::TrailingStopPercent { trail: 250 });
+                assert_eq!(trigger_price, Some(49000));
+            }
+            _ => panic!("expected Ack"),
+        }
+    }
+
+    #[test]
+    fn test_triggered_event_serialization_round_trips() {
+        let triggered = WSEvent::OrderUpdate(UserOrderUpdateEvent::Triggered {
+            order_id: 1,
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            trigger_price: 49000,
+            timestamp: 1000,
+            seq: 0,
+        });
+
+        assert_eq!(triggered.user_id(), Some(100));
+        assert_eq!(triggered.symbol(), Some("SOL_USDC"));
+
+        let json = serde_json::to_string(&triggered).unwrap();
+        let deserialized: WSEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(triggered, deserialized);
+    }
+
+    // ========== Subscription Tests ==========
+
+    fn sample_trade(symbol: &str) -> WSEvent {
+        WSEvent::Trade(TradeEvent {
+            trade_id: 1,
+            symbol: symbol.to_string(),
+            price: 50000,
+            quantity: 10,
+            timestamp: 1000,
+            seq: 0,
+        })
+    }
+
+    fn sample_depth(symbol: &str) -> WSEvent {
+        WSEvent::Depth(DepthEvent {
+            symbol: symbol.to_string(),
+            bids: vec![],
+            asks: vec![],
+            last_price: None,
+            timestamp: 1000,
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
+        })
+    }
+
+    fn sample_order_update(user_id: u64) -> WSEvent {
+        WSEvent::OrderUpdate(UserOrderUpdateEvent::Ack {
+            order_id: 1,
+            user_id,
+            symbol: "SOL_USDC".to_string(),
+            order_type: OrderType::Limit,
+            trigger_price: None,
+            timestamp: 1000,
+            seq: 0,
+        })
+    }
+
+    fn sample_symbol_info(symbol: &str) -> WSEvent {
+        WSEvent::SymbolInfo(SymbolInfoEvent {
+            symbol: symbol.to_string(),
+            base_asset: "SOL".to_string(),
+            quote_asset: "USDC".to_string(),
+            base_precision: 4,
+            quote_precision: 2,
+            filters: vec![],
+        })
+    }
+
+    #[test]
+    fn test_subscription_everything_matches_any_event() {
+        let sub = Subscription::everything();
+
+        assert!(sub.matches(&sample_trade("SOL_USDC")));
+        assert!(sub.matches(&sample_depth("BTC/USD")));
+        assert!(sub.matches(&sample_order_update(42)));
+    }
+
+    #[test]
+    fn test_subscription_channel_wildcard_symbol() {
+        let sub = Subscription::channel(Channel::Trade);
+
+        assert!(sub.matches(&sample_trade("SOL_USDC")));
+        assert!(sub.matches(&sample_trade("BTC/USD")));
+        assert!(!sub.matches(&sample_depth("SOL_USDC")));
+    }
+
+    #[test]
+    fn test_subscription_channel_and_symbol_is_exact() {
+        let sub = Subscription::channel_and_symbol(Channel::Trade, "SOL_USDC");
+
+        assert!(sub.matches(&sample_trade("SOL_USDC")));
+        assert!(!sub.matches(&sample_trade("BTC/USD")));
+        assert!(!sub.matches(&sample_depth("SOL_USDC")));
+    }
+
+    #[test]
+    fn test_subscription_symbol_info_channel() {
+        let sub = Subscription::channel(Channel::SymbolInfo);
+
+        assert!(sub.matches(&sample_symbol_info("SOL_USDC")));
+        assert!(!sub.matches(&sample_trade("SOL_USDC")));
+    }
+
+    #[test]
+    fn test_subscription_symbol_wildcard_star() {
+        let sub = Subscription::channel_and_symbol(Channel::Depth, "*");
+
+        assert!(sub.matches(&sample_depth("SOL_USDC")));
+        assert!(sub.matches(&sample_depth("BTC/USD")));
+    }
+
+    #[test]
+    fn test_subscription_order_update_is_scoped_to_user() {
+        let sub = Subscription::channel(Channel::OrderUpdate(100));
+
+        assert!(sub.matches(&sample_order_update(100)));
+        assert!(!sub.matches(&sample_order_update(200)));
+        assert!(!sub.matches(&sample_trade("SOL_USDC")));
+    }
+
+    // Publisher that only receives what it explicitly subscribed to, used
+    // to test pipeline routing without assuming MockPublisher's
+    // everything-by-default behavior.
+    #[derive(Clone)]
+    struct RoutedPublisher {
+        subscriptions: Arc<Mutex<Vec<Subscription>>>,
+        published: Arc<Mutex<Vec<WSEvent>>>,
+    }
+
+    impl RoutedPublisher {
+        fn new() -> Self {
+            Self {
+                subscriptions: Arc::new(Mutex::new(Vec::new())),
+                published: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        fn get_published(&self) -> Vec<WSEvent> {
+            self.published.lock().unwrap().clone()
+        }
+    }
+
+    impl Publisher for RoutedPublisher {
+        fn publish(&self, event: &WSEvent) {
+            self.published.lock().unwrap().push(event.clone());
+        }
+
+        fn publish_batch(&self, events: Vec<WSEvent>) {
+            self.published.lock().unwrap().extend(events);
+        }
+
+        fn subscribe(&self, subscription: Subscription) {
+            self.subscriptions.lock().unwrap().push(subscription);
+        }
+
+        fn unsubscribe(&self, subscription: &Subscription) {
+            self.subscriptions.lock().unwrap().retain(|s| s != subscription);
+        }
+
+        fn is_subscribed(&self, event: &WSEvent) -> bool {
+            self.subscriptions
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|s| s.matches(event))
+        }
+    }
+
+    #[test]
+    fn test_pipeline_routes_only_subscribed_events() {
+        let routed = RoutedPublisher::new();
+        routed.subscribe(Subscription::channel_and_symbol(Channel::Trade, "SOL_USDC"));
+
+        let publishers: Vec<Box<dyn Publisher>> = vec![Box::new(routed.clone())];
+        let mut pipeline = MarketDataPipeline::new(publishers);
+
+        let (event_tx, event_rx) = crossbeam_channel::unbounded::<Event>();
+        let handle = std::thread::spawn(move || {
+            pipeline.run(event_rx);
+        });
+
+        event_tx
+            .send(Event::Trade(Trade {
+                trade_id: 1,
+                maker_order_id: 10,
+                maker_user_id: 100,
+                taker_order_id: 20,
+                taker_user_id: 200,
+                symbol: "SOL_USDC".to_string(),
+                quantity: 50,
+                price: 50000,
+                maker_fee: 0,
+                taker_fee: 0,
+                timestamp: 1234567890,
+            }))
+            .unwrap();
+
+        event_tx
+            .send(Event::BookUpdate(BookUpdate {
+                symbol: "BTC/USD".to_string(),
+                bids: vec![],
+                asks: vec![],
+                last_price: Some(60000),
+            }))
+            .unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        drop(event_tx);
+        handle.join().unwrap();
+
+        let published = routed.get_published();
+        assert!(
+            published
+                .iter()
+                .any(|e| matches!(e, WSEvent::Trade(t) if t.symbol == "SOL_USDC")),
+            "Should have published the subscribed SOL_USDC trade"
+        );
+        assert!(
+            !published.iter().any(|e| matches!(e, WSEvent::Depth(_))),
+            "Should not have published the unsubscribed BTC/USD depth"
+        );
+    }
 }
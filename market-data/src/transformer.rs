@@ -1,6 +1,9 @@
-use crate::types::{DepthEvent, Event as WsEvent, TradeEvent, UserOrderUpdateEvent};
+use crate::types::{
+    DepthEvent, Event as WsEvent, FillUpdateStatus, OrderType, TradeEvent, UserOrderUpdateEvent,
+};
 use protocol::types::{
-    BookUpdate, Event as EngineEvent, Fill, OrderAck, OrderCancelled, OrderReject, Trade,
+    BookUpdate, BulkCancelSummary, Event as EngineEvent, Fill, OrderAck, OrderCancelled,
+    OrderReject, OrderTriggered, OrderType as EngineOrderType, Price, Trade,
 };
 
 pub struct Transformer;
@@ -23,6 +26,8 @@ impl Transformer {
             EngineEvent::OrderCancelled(order_cancelled) => {
                 self.transform_order_cancelled(order_cancelled)
             }
+            EngineEvent::BulkCancelSummary(summary) => self.transform_bulk_cancel_summary(summary),
+            EngineEvent::OrderTriggered(triggered) => self.transform_order_triggered(triggered),
         }
     }
 
@@ -33,6 +38,8 @@ impl Transformer {
             price: trade.price,
             quantity: trade.quantity,
             timestamp: trade.timestamp,
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
         })
     }
 
@@ -43,6 +50,11 @@ impl Transformer {
             asks: book_update.asks,
             last_price: book_update.last_price,
             timestamp: chrono::Utc::now().timestamp_millis(),
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
+            first_seq: 0,
+            last_seq: 0,
+            is_snapshot: false,
         })
     }
 
@@ -51,22 +63,62 @@ impl Transformer {
             order_id: fill.order_id,
             user_id: fill.user_id,
             symbol: fill.symbol,
+            trade_id: fill.trade_id,
+            // Every fill transformed straight off the engine stream is new;
+            // `Aggregator::revoke_fill` is what produces a `Revoke`.
+            status: FillUpdateStatus::New,
             filled_quantity: fill.filled_quantity,
             filled_price: fill.filled_price,
             remaining_quantity: fill.remaining_quantity,
             timestamp: chrono::Utc::now().timestamp_millis(),
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
         })
     }
 
     pub fn transform_order_ack(&self, order_ack: OrderAck) -> WsEvent {
+        let (order_type, trigger_price) = Self::map_order_type(order_ack.order_type);
         WsEvent::OrderUpdate(UserOrderUpdateEvent::Ack {
             order_id: order_ack.order_id,
             user_id: order_ack.user_id,
             symbol: order_ack.symbol,
+            order_type,
+            trigger_price,
             timestamp: chrono::Utc::now().timestamp_millis(),
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
         })
     }
 
+    /// Maps the engine's internal `OrderType` (which carries a conditional
+    /// order's trigger/limit/offset inline) to the WS-facing `OrderType`
+    /// (which doesn't) plus the `trigger_price` an `Ack` reports alongside
+    /// it. Trailing types have no fixed trigger price to report since it
+    /// moves with the market, so they surface `None`.
+    fn map_order_type(order_type: EngineOrderType) -> (OrderType, Option<Price>) {
+        match order_type {
+            EngineOrderType::Market => (OrderType::Market, None),
+            EngineOrderType::Limit
+            | EngineOrderType::PostOnly
+            | EngineOrderType::ImmediateOrCancel
+            | EngineOrderType::FillOrKill => (OrderType::Limit, None),
+            EngineOrderType::Stop { trigger } => (OrderType::StopMarket, Some(trigger)),
+            EngineOrderType::StopLimit { trigger, .. } => (OrderType::StopLimit, Some(trigger)),
+            EngineOrderType::TrailingStop { offset } => (
+                OrderType::TrailingStopAmount {
+                    trail: offset as i64,
+                },
+                None,
+            ),
+            EngineOrderType::TrailingStopPct { pct_bps } => (
+                OrderType::TrailingStopPercent {
+                    trail: pct_bps as i64,
+                },
+                None,
+            ),
+        }
+    }
+
     pub fn transform_order_reject(&self, order_reject: OrderReject) -> WsEvent {
         WsEvent::OrderUpdate(UserOrderUpdateEvent::Reject {
             order_id: order_reject.order_id,
@@ -75,6 +127,8 @@ impl Transformer {
             message: order_reject.message,
             timestamp: chrono::Utc::now().timestamp_millis(),
             symbol: order_reject.symbol,
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
         })
     }
 
@@ -84,6 +138,31 @@ impl Transformer {
             user_id: order_cancelled.user_id,
             symbol: order_cancelled.symbol,
             timestamp: chrono::Utc::now().timestamp_millis(),
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
+        })
+    }
+
+    pub fn transform_order_triggered(&self, triggered: OrderTriggered) -> WsEvent {
+        WsEvent::OrderUpdate(UserOrderUpdateEvent::Triggered {
+            order_id: triggered.order_id,
+            user_id: triggered.user_id,
+            symbol: triggered.symbol,
+            trigger_price: triggered.trigger_price,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
+        })
+    }
+
+    pub fn transform_bulk_cancel_summary(&self, summary: BulkCancelSummary) -> WsEvent {
+        WsEvent::OrderUpdate(UserOrderUpdateEvent::BulkCancelled {
+            user_id: summary.user_id,
+            symbol: summary.symbol.unwrap_or_default(),
+            cancelled_count: summary.cancelled_count,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            // Stamped by `MarketDataPipeline` just before publishing.
+            seq: 0,
         })
     }
 }
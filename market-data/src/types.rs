@@ -0,0 +1,394 @@
+use protocol::types::{OrderId, OrderStatus, Price, PriceLevel, Quantity, UserId};
+use serde::{Deserialize, Serialize};
+
+/// Public market-data and private order-lifecycle events published over the
+/// WS feed. Produced by `Transformer` from engine `protocol::types::Event`s
+/// and enriched (ticker/kline rollups, depth throttling) by `Aggregator`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    Trade(TradeEvent),
+    Depth(DepthEvent),
+    Ticker(TickerEvent),
+    BookTicker(BookTickerEvent),
+    Kline(KlineEvent),
+    SymbolInfo(SymbolInfoEvent),
+    OrderUpdate(UserOrderUpdateEvent),
+}
+
+impl Event {
+    /// Whether this event belongs on a public channel anyone can subscribe
+    /// to, as opposed to a single user's private order-update stream.
+    pub fn is_public(&self) -> bool {
+        !matches!(self, Event::OrderUpdate(_))
+    }
+
+    /// The user this event is scoped to, if it's a private event.
+    pub fn user_id(&self) -> Option<UserId> {
+        match self {
+            Event::OrderUpdate(update) => Some(update.user_id()),
+            _ => None,
+        }
+    }
+
+    /// The symbol this event concerns.
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Event::Trade(t) => Some(&t.symbol),
+            Event::Depth(d) => Some(&d.symbol),
+            Event::Ticker(t) => Some(&t.symbol),
+            Event::BookTicker(b) => Some(&b.symbol),
+            Event::Kline(k) => Some(&k.symbol),
+            Event::SymbolInfo(s) => Some(&s.symbol),
+            Event::OrderUpdate(update) => Some(update.symbol()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeEvent {
+    pub trade_id: u64,
+    pub symbol: String,
+    pub price: Price,
+    pub quantity: Quantity,
+    pub timestamp: i64,
+    /// Monotonic per-symbol sequence number, stamped by `MarketDataPipeline`
+    /// just before publishing. `0` until then.
+    pub seq: u64,
+}
+
+/// A depth update as seen by a WS consumer: either a full order-book
+/// `is_snapshot` (pulled via `MarketDataPipeline::depth_snapshot` on initial
+/// subscribe or resync) or an incremental diff (published periodically by
+/// `Aggregator`). On a diff, `bids`/`asks` contain only the price levels
+/// that changed since the last diff — a quantity of `0` means "remove this
+/// level" — not the full book. `first_seq..=last_seq` is the range of raw
+/// book updates this event covers (more than one when prior updates were
+/// throttled/conflated away).
+///
+/// To reconstruct a book: buffer incoming diffs, fetch a snapshot, discard
+/// any buffered diff whose `last_seq <= snapshot.last_seq`, then apply the
+/// rest in order, each one layering its levels onto the snapshot (deleting
+/// levels with quantity `0`). Before applying a diff, check that its
+/// `first_seq` is the previous applied event's `last_seq + 1` — if not, a
+/// diff was missed and the client must re-fetch a snapshot and resync. See
+/// [`is_sequence_gap`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DepthEvent {
+    pub symbol: String,
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+    pub last_price: Option<Price>,
+    pub timestamp: i64,
+    pub seq: u64,
+    pub first_seq: u64,
+    pub last_seq: u64,
+    pub is_snapshot: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TickerEvent {
+    pub symbol: String,
+    pub last_price: Price,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub volume: Quantity,
+    pub price_change: i64,
+    pub price_change_percent: f64,
+    pub timestamp: i64,
+    pub seq: u64,
+}
+
+/// The best bid/offer derived from the top level of a `DepthEvent`'s
+/// `bids`/`asks`, for a client that only wants top-of-book and not the full
+/// depth diff stream. Emitted by `Aggregator` only when the BBO actually
+/// changes, not on every depth tick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookTickerEvent {
+    pub symbol: String,
+    pub best_bid: Price,
+    pub best_bid_qty: Quantity,
+    pub best_ask: Price,
+    pub best_ask_qty: Quantity,
+    pub timestamp: i64,
+    pub seq: u64,
+}
+
+/// The bucket width of a `KlineEvent`, mirroring the interval sets upstream
+/// candlestick feeds expose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KlineInterval {
+    OneMinute,
+    FiveMinutes,
+    OneHour,
+    OneDay,
+}
+
+impl KlineInterval {
+    /// This interval's bucket width in milliseconds.
+    pub fn as_millis(&self) -> i64 {
+        const MINUTE: i64 = 60_000;
+        match self {
+            KlineInterval::OneMinute => MINUTE,
+            KlineInterval::FiveMinutes => 5 * MINUTE,
+            KlineInterval::OneHour => 60 * MINUTE,
+            KlineInterval::OneDay => 24 * 60 * MINUTE,
+        }
+    }
+}
+
+/// One OHLCV candlestick bucket for a `(symbol, interval)` pair, spanning
+/// `[open_time, close_time]`. `closed` is `false` while the bucket is still
+/// accumulating trades and flips to `true` exactly once, in the event
+/// emitted when the next trade rolls over into a new bucket.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KlineEvent {
+    pub symbol: String,
+    pub interval: KlineInterval,
+    pub open_time: i64,
+    pub close_time: i64,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Quantity,
+    pub trade_count: u64,
+    pub closed: bool,
+}
+
+/// A tradable-range or increment constraint on a symbol's orders, mirroring
+/// the exchange filter sets (e.g. Binance's `PRICE_FILTER`/`LOT_SIZE`/
+/// `MIN_NOTIONAL`) clients validate order entry against before submitting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymbolFilter {
+    PriceFilter {
+        min_price: Price,
+        max_price: Price,
+        tick_size: Price,
+    },
+    LotSize {
+        min_qty: Quantity,
+        max_qty: Quantity,
+        step_size: Quantity,
+    },
+    MinNotional {
+        min_notional: Price,
+    },
+}
+
+/// A symbol's metadata: its assets, the decimal precision its scaled
+/// integer prices/quantities are denominated in, and the filters an order
+/// must satisfy. Lets a subscriber format and validate order entry without
+/// a hardcoded symbol table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolInfoEvent {
+    pub symbol: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub base_precision: u32,
+    pub quote_precision: u32,
+    pub filters: Vec<SymbolFilter>,
+}
+
+impl SymbolInfoEvent {
+    /// Scales a raw integer price (as carried on `TradeEvent`/`DepthEvent`/
+    /// etc.) into a human-readable decimal, using this symbol's
+    /// `quote_precision`.
+    pub fn scale_price(&self, raw: Price) -> f64 {
+        raw as f64 / 10f64.powi(self.quote_precision as i32)
+    }
+}
+
+/// Whether a `UserOrderUpdateEvent::Fill` is adding to an order's execution
+/// (`New`) or undoing a previously published one that turned out not to
+/// have happened after all (`Revoke`), e.g. a reorg'd or rejected
+/// settlement. A consumer matches a `Revoke` to the `New` it cancels out by
+/// `(order_id, trade_id)` and subtracts the same quantity/price it had
+/// added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillUpdateStatus {
+    New,
+    Revoke,
+}
+
+/// How a resting order enters the book and what triggers it, as surfaced to
+/// WS subscribers. Distinct from `protocol::types::OrderType`, which governs
+/// matching-engine behavior (`PostOnly`/`ImmediateOrCancel`/`FillOrKill`)
+/// rather than display/triggering semantics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderType {
+    Limit,
+    Market,
+    StopLimit,
+    StopMarket,
+    LimitIfTouched,
+    MarketIfTouched,
+    /// Arms when the market moves `trail` away from its best price since
+    /// the order was placed, in absolute price units.
+    TrailingStopAmount { trail: i64 },
+    /// Arms when the market moves `trail` percent (in basis points) away
+    /// from its best price since the order was placed.
+    TrailingStopPercent { trail: i64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UserOrderUpdateEvent {
+    Ack {
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        order_type: OrderType,
+        /// The price that arms a conditional order into a live limit/market
+        /// order. `None` for `Limit`/`Market`, which need no trigger.
+        trigger_price: Option<Price>,
+        timestamp: i64,
+        seq: u64,
+    },
+    Reject {
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        reason: String,
+        message: String,
+        timestamp: i64,
+        seq: u64,
+    },
+    Cancelled {
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        timestamp: i64,
+        seq: u64,
+    },
+    /// Emitted the moment a conditional (stop/trailing) order's trigger
+    /// condition is met and it arms into a live limit/market order, so a
+    /// subscriber can distinguish a resting conditional order from an
+    /// active one.
+    Triggered {
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        trigger_price: Price,
+        timestamp: i64,
+        seq: u64,
+    },
+    Fill {
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        /// Uniquely identifies this fill within the order, so a later
+        /// `Revoke` can be matched back to the `New` it undoes.
+        trade_id: u64,
+        status: FillUpdateStatus,
+        filled_quantity: Quantity,
+        filled_price: Price,
+        remaining_quantity: Quantity,
+        timestamp: i64,
+        seq: u64,
+    },
+    /// The authoritative cumulative execution state of one order, derived by
+    /// `Aggregator` by summing successive `Fill`s (and folding in
+    /// `Cancelled`/`Reject`) so a client doesn't have to replay the raw
+    /// update stream to know how much of an order has filled so far.
+    Status {
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        cumulative_filled: Quantity,
+        remaining: Quantity,
+        average_fill_price: Option<Price>,
+        state: OrderStatus,
+        seq: u64,
+    },
+    /// Reported once per bulk/user-scoped cancel command, alongside the
+    /// individual `Cancelled` events, so a client doesn't have to count them
+    /// to know how many of its orders were actually live. `symbol` is empty
+    /// when the cancellation wasn't scoped to one market.
+    BulkCancelled {
+        user_id: UserId,
+        symbol: String,
+        cancelled_count: usize,
+        timestamp: i64,
+        seq: u64,
+    },
+    /// The full set of `user_id`'s currently-open orders across every
+    /// market, so a client that just (re)subscribed to its private stream
+    /// (or just received an `Ack`/`Fill`/`Cancelled`) can reconstruct its
+    /// open-order book without replaying the raw update history. `symbol` is
+    /// always empty: this snapshot isn't scoped to one market, unlike every
+    /// other variant.
+    OpenOrders {
+        user_id: UserId,
+        symbol: String,
+        orders: Vec<OpenOrder>,
+        timestamp: i64,
+        seq: u64,
+    },
+}
+
+/// One resting order in a user's open-order snapshot, as reported by
+/// `UserOrderUpdateEvent::OpenOrders`. Carries only what an `Ack` and its
+/// subsequent `Fill`s actually report — an `Ack` has no price/quantity of
+/// its own, so neither does this.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenOrder {
+    pub order_id: OrderId,
+    pub symbol: String,
+    pub order_type: OrderType,
+    pub trigger_price: Option<Price>,
+    pub cumulative_filled: Quantity,
+    pub average_fill_price: Option<Price>,
+    pub opened_at: i64,
+}
+
+impl UserOrderUpdateEvent {
+    pub fn user_id(&self) -> UserId {
+        match self {
+            UserOrderUpdateEvent::Ack { user_id, .. }
+            | UserOrderUpdateEvent::Reject { user_id, .. }
+            | UserOrderUpdateEvent::Cancelled { user_id, .. }
+            | UserOrderUpdateEvent::Triggered { user_id, .. }
+            | UserOrderUpdateEvent::Fill { user_id, .. }
+            | UserOrderUpdateEvent::Status { user_id, .. }
+            | UserOrderUpdateEvent::BulkCancelled { user_id, .. }
+            | UserOrderUpdateEvent::OpenOrders { user_id, .. } => *user_id,
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        match self {
+            UserOrderUpdateEvent::Ack { symbol, .. }
+            | UserOrderUpdateEvent::Reject { symbol, .. }
+            | UserOrderUpdateEvent::Cancelled { symbol, .. }
+            | UserOrderUpdateEvent::Triggered { symbol, .. }
+            | UserOrderUpdateEvent::Fill { symbol, .. }
+            | UserOrderUpdateEvent::Status { symbol, .. }
+            | UserOrderUpdateEvent::BulkCancelled { symbol, .. }
+            | UserOrderUpdateEvent::OpenOrders { symbol, .. } => symbol,
+        }
+    }
+
+    /// Stamps this event's per-(user, symbol) sequence number, overwriting whatever
+    /// placeholder value it was constructed with.
+    pub fn set_seq(&mut self, seq: u64) {
+        match self {
+            UserOrderUpdateEvent::Ack { seq: s, .. }
+            | UserOrderUpdateEvent::Reject { seq: s, .. }
+            | UserOrderUpdateEvent::Cancelled { seq: s, .. }
+            | UserOrderUpdateEvent::Triggered { seq: s, .. }
+            | UserOrderUpdateEvent::Fill { seq: s, .. }
+            | UserOrderUpdateEvent::Status { seq: s, .. }
+            | UserOrderUpdateEvent::BulkCancelled { seq: s, .. }
+            | UserOrderUpdateEvent::OpenOrders { seq: s, .. } => *s = seq,
+        }
+    }
+}
+
+/// Returns whether observing `seq` right after previously seeing `prev_seq`
+/// means one or more updates were missed in between, i.e. `seq` isn't
+/// `prev_seq`'s immediate successor. For a `DepthEvent` diff, compare its
+/// `first_seq` against the previous event's `last_seq` instead.
+pub fn is_sequence_gap(prev_seq: u64, seq: u64) -> bool {
+    seq != prev_seq + 1
+}
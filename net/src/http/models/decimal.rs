@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use protocol::types::{Price, Quantity};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+/// A tick amount can carry at most this many fractional digits; beyond this
+/// a `u64` can no longer hold the scaled value for any realistic integer
+/// part, so parsing rejects it outright rather than silently truncating.
+const MAX_DECIMALS: u32 = 18;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DecimalError {
+    #[error("decimal string has {0} fractional digits, more than the {MAX_DECIMALS} allowed")]
+    TooManyFractionalDigits(usize),
+
+    #[error("decimal string is not a valid number: {0}")]
+    InvalidFormat(String),
+
+    #[error("scaling the decimal value overflowed u64")]
+    Overflow,
+}
+
+/// A raw tick amount (as stored on `OrderBook`/`OrderRequest`) paired with
+/// the number of decimal places it should render with, e.g. `5000000` at
+/// 5 decimals serializes as `"50.00000"` instead of the bare integer.
+///
+/// Deserializing infers `decimals` from the fractional digit count of the
+/// string itself, so no external scale lookup is needed to read a value
+/// back; `Scale` is only needed when *producing* a `DecimalU64` from a raw
+/// tick amount on the way out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalU64 {
+    pub raw: u64,
+    pub decimals: u8,
+}
+
+impl DecimalU64 {
+    pub fn new(raw: u64, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    /// Formats `raw` as a decimal string with exactly `decimals` fractional
+    /// digits, e.g. `DecimalU64::new(5000000, 5).to_decimal_string() == "50.00000"`.
+    pub fn to_decimal_string(&self) -> String {
+        if self.decimals == 0 {
+            return self.raw.to_string();
+        }
+
+        let scale = 10u64.pow(self.decimals as u32);
+        let integer_part = self.raw / scale;
+        let fractional_part = self.raw % scale;
+
+        format!(
+            "{integer_part}.{fractional_part:0width$}",
+            width = self.decimals as usize
+        )
+    }
+
+    /// Parses a decimal string back into raw ticks, inferring `decimals`
+    /// from the number of digits after the `.`. Losslessly reverses
+    /// `to_decimal_string` by multiplying the integer and fractional parts
+    /// by `10^decimals` and summing them.
+    pub fn from_decimal_str(s: &str) -> Result<Self, DecimalError> {
+        let (integer_part, fractional_part) = match s.split_once('.') {
+            Some((int, frac)) => (int, frac),
+            None => (s, ""),
+        };
+
+        if fractional_part.len() > MAX_DECIMALS as usize {
+            return Err(DecimalError::TooManyFractionalDigits(
+                fractional_part.len(),
+            ));
+        }
+
+        let integer_value: u64 = integer_part
+            .parse()
+            .map_err(|_| DecimalError::InvalidFormat(s.to_string()))?;
+        let fractional_value: u64 = if fractional_part.is_empty() {
+            0
+        } else {
+            fractional_part
+                .parse()
+                .map_err(|_| DecimalError::InvalidFormat(s.to_string()))?
+        };
+
+        let decimals = fractional_part.len() as u8;
+        let scale = 10u64.pow(decimals as u32);
+
+        let raw = integer_value
+            .checked_mul(scale)
+            .and_then(|scaled_integer| scaled_integer.checked_add(fractional_value))
+            .ok_or(DecimalError::Overflow)?;
+
+        Ok(Self { raw, decimals })
+    }
+}
+
+impl fmt::Display for DecimalU64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_decimal_string())
+    }
+}
+
+impl Serialize for DecimalU64 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_decimal_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DecimalU64 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        DecimalU64::from_decimal_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The decimal precision a market's price and quantity ticks should be
+/// rendered at, e.g. `Scale { price_decimals: 5, qty_decimals: 3 }` turns a
+/// `Price` tick of `5000000` into `"50.00000"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scale {
+    pub price_decimals: u8,
+    pub qty_decimals: u8,
+}
+
+impl Scale {
+    /// No scaling at all: ticks render as the bare integer, matching the
+    /// raw-tick wire format clients relied on before this module existed.
+    pub const RAW: Scale = Scale {
+        price_decimals: 0,
+        qty_decimals: 0,
+    };
+
+    pub fn to_price(&self, price: Price) -> DecimalU64 {
+        DecimalU64::new(price, self.price_decimals)
+    }
+
+    pub fn to_quantity(&self, quantity: Quantity) -> DecimalU64 {
+        DecimalU64::new(quantity, self.qty_decimals)
+    }
+}
+
+/// Runtime registry mapping a market symbol to the `Scale` its price and
+/// quantity ticks should be rendered at, mirroring `MarketRegistry`'s
+/// per-symbol lookup for market parameters. A symbol with no registered
+/// scale falls back to `Scale::RAW`, so integrations that never opt in to
+/// decimal rendering keep seeing bare integer ticks.
+#[derive(Debug, Default)]
+pub struct ScaleRegistry {
+    scales: HashMap<String, Scale>,
+}
+
+impl ScaleRegistry {
+    pub fn new() -> Self {
+        Self {
+            scales: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, symbol: &str, scale: Scale) {
+        self.scales.insert(symbol.to_string(), scale);
+    }
+
+    pub fn get(&self, symbol: &str) -> Scale {
+        self.scales.get(symbol).copied().unwrap_or(Scale::RAW)
+    }
+}
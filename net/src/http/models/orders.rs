@@ -0,0 +1,342 @@
+use std::collections::BTreeMap;
+
+use http::{Response, StatusCode};
+use protocol::types::{
+    OrderId, OrderStatus, OrderType, Price, Quantity, RejectReason, SelfTradeBehavior, Side,
+    TimeInForce, UserId,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::http::models::decimal::{DecimalU64, Scale};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderRequest {
+    pub user_id: UserId,
+    pub symbol: String,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub quantity: Quantity,
+    pub price: Option<Price>,
+    pub self_trade_behavior: SelfTradeBehavior,
+    pub time_in_force: TimeInForce,
+    /// Client-chosen idempotency key. Resubmitting the same id returns the
+    /// original `Ack` instead of placing a second order.
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+}
+
+/// Wire-compatible twin of `OrderRequest` for integrations that want
+/// human-readable decimal strings (e.g. `"50.00000"`) instead of raw ticks.
+/// Raw-tick clients keep submitting plain `OrderRequest`; this is opt-in,
+/// selected by the caller rather than a global switch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecimalOrderRequest {
+    pub user_id: UserId,
+    pub symbol: String,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub quantity: DecimalU64,
+    pub price: Option<DecimalU64>,
+    pub self_trade_behavior: SelfTradeBehavior,
+    pub time_in_force: TimeInForce,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+}
+
+impl DecimalOrderRequest {
+    /// Converts to the raw-tick `OrderRequest` the rest of the system
+    /// expects. `decimals` are inferred per-field from each decimal string,
+    /// so no `Scale` lookup is needed here, only when rendering back out.
+    pub fn into_order_request(self) -> OrderRequest {
+        OrderRequest {
+            user_id: self.user_id,
+            symbol: self.symbol,
+            side: self.side,
+            order_type: self.order_type,
+            quantity: self.quantity.raw,
+            price: self.price.map(|price| price.raw),
+            self_trade_behavior: self.self_trade_behavior,
+            time_in_force: self.time_in_force,
+            client_order_id: self.client_order_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderResponse {
+    Ack {
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        /// Echoes the request's idempotency key, if one was supplied.
+        client_order_id: Option<String>,
+    },
+    Reject {
+        order_id: OrderId,
+        reason: RejectReason,
+        symbol: String,
+        message: String,
+        client_order_id: Option<String>,
+    },
+    /// Reports execution progress for a marketable order: how much of it has
+    /// filled so far, at what average price, and the resulting lifecycle
+    /// status. A single order crossing multiple book levels is reported as
+    /// one `Fill` carrying the cumulative totals, not one per level.
+    Fill {
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        status: OrderStatus,
+        filled_quantity: Quantity,
+        remaining_quantity: Quantity,
+        avg_fill_price: Option<Price>,
+        client_order_id: Option<String>,
+    },
+}
+
+impl OrderResponse {
+    /// Builds a `Fill` response, deriving `status` and `remaining_quantity`
+    /// from `filled_quantity` so the `filled + remaining == original`
+    /// invariant always holds by construction.
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill(
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        original_quantity: Quantity,
+        filled_quantity: Quantity,
+        avg_fill_price: Option<Price>,
+        client_order_id: Option<String>,
+    ) -> Self {
+        let remaining_quantity = original_quantity.saturating_sub(filled_quantity);
+        let status = if remaining_quantity == 0 {
+            OrderStatus::Filled
+        } else if filled_quantity > 0 {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::Pending
+        };
+
+        OrderResponse::Fill {
+            order_id,
+            user_id,
+            symbol,
+            status,
+            filled_quantity,
+            remaining_quantity,
+            avg_fill_price,
+            client_order_id,
+        }
+    }
+
+    pub fn into_http_response(self) -> Response<String> {
+        let status = match &self {
+            OrderResponse::Ack { .. } => StatusCode::OK,
+            OrderResponse::Reject { .. } => StatusCode::BAD_REQUEST,
+            OrderResponse::Fill { .. } => StatusCode::OK,
+        };
+
+        let body = serde_json::to_string(&self).unwrap_or_default();
+
+        Response::builder()
+            .status(status)
+            .body(body)
+            .expect("status and body are always valid")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CancelOrderRequest {
+    pub user_id: UserId,
+    pub symbol: String,
+    /// The server-assigned order id to cancel. May be omitted if
+    /// `client_order_id` is supplied instead.
+    #[serde(default)]
+    pub order_id: Option<OrderId>,
+    /// Cancels by the id the client originally submitted the order with,
+    /// resolved against the dedup map instead of `order_id`.
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CancelOrderResponse {
+    Ack {
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        client_order_id: Option<String>,
+    },
+    Reject {
+        order_id: OrderId,
+        reason: RejectReason,
+        message: String,
+        client_order_id: Option<String>,
+    },
+}
+
+impl CancelOrderResponse {
+    pub fn into_http_response(self) -> Response<String> {
+        let status = match &self {
+            CancelOrderResponse::Ack { .. } => StatusCode::OK,
+            CancelOrderResponse::Reject { .. } => StatusCode::BAD_REQUEST,
+        };
+
+        let body = serde_json::to_string(&self).unwrap_or_default();
+
+        Response::builder()
+            .status(status)
+            .body(body)
+            .expect("status and body are always valid")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthQuery {
+    pub limit: usize,
+    /// Drop levels priced below this, applied before `limit`.
+    #[serde(default)]
+    pub min_price: Option<Price>,
+    /// Drop levels priced above this, applied before `limit`.
+    #[serde(default)]
+    pub max_price: Option<Price>,
+    /// Drop levels whose quantity is below this, applied before `limit`.
+    #[serde(default)]
+    pub min_quantity: Option<Quantity>,
+    /// Bucket levels into multiples of this tick, summing quantity within
+    /// each bucket, before `limit` is applied. `None` returns raw levels.
+    #[serde(default)]
+    pub aggregate_tick: Option<Price>,
+}
+
+impl DepthQuery {
+    /// Builds a filtered, optionally aggregated `DepthResponse` from raw
+    /// bid/ask levels. Filters and aggregation are applied before the
+    /// `limit` truncation, so the result is the top `limit` levels that
+    /// pass the query rather than `limit` raw levels with some discarded.
+    pub fn apply(&self, bids: Vec<(Price, Quantity)>, asks: Vec<(Price, Quantity)>) -> DepthResponse {
+        DepthResponse {
+            bids: self.build_side(bids, true),
+            asks: self.build_side(asks, false),
+            resolution: self.aggregate_tick.unwrap_or(0),
+        }
+    }
+
+    fn passes_filter(&self, price: Price, quantity: Quantity) -> bool {
+        if let Some(min_price) = self.min_price {
+            if price < min_price {
+                return false;
+            }
+        }
+        if let Some(max_price) = self.max_price {
+            if price > max_price {
+                return false;
+            }
+        }
+        if let Some(min_quantity) = self.min_quantity {
+            if quantity < min_quantity {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn build_side(&self, levels: Vec<(Price, Quantity)>, is_bid: bool) -> Vec<(Price, Quantity)> {
+        let filtered = levels
+            .into_iter()
+            .filter(|&(price, quantity)| self.passes_filter(price, quantity));
+
+        let levels: Vec<(Price, Quantity)> = match self.aggregate_tick {
+            Some(tick) if tick > 0 => {
+                let mut buckets: BTreeMap<Price, Quantity> = BTreeMap::new();
+                for (price, quantity) in filtered {
+                    let bucket = (price / tick) * tick;
+                    *buckets.entry(bucket).or_insert(0) += quantity;
+                }
+                if is_bid {
+                    buckets.into_iter().rev().collect()
+                } else {
+                    buckets.into_iter().collect()
+                }
+            }
+            _ => filtered.collect(),
+        };
+
+        levels.into_iter().take(self.limit).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DepthResponse {
+    pub bids: Vec<(Price, Quantity)>,
+    pub asks: Vec<(Price, Quantity)>,
+    /// The tick the levels were bucketed into, or `0` for unaggregated,
+    /// full-resolution levels.
+    #[serde(default)]
+    pub resolution: Price,
+}
+
+impl DepthResponse {
+    /// Renders this response's raw ticks as human-readable decimal strings
+    /// at `scale`'s precision, for integrations that opt in to the decimal
+    /// wire format instead of raw integers.
+    pub fn to_decimal(&self, scale: Scale) -> DecimalDepthResponse {
+        DecimalDepthResponse {
+            bids: self
+                .bids
+                .iter()
+                .map(|&(price, quantity)| (scale.to_price(price), scale.to_quantity(quantity)))
+                .collect(),
+            asks: self
+                .asks
+                .iter()
+                .map(|&(price, quantity)| (scale.to_price(price), scale.to_quantity(quantity)))
+                .collect(),
+            resolution: scale.to_price(self.resolution),
+        }
+    }
+}
+
+/// Wire-compatible twin of `DepthResponse` with human-readable decimal
+/// strings instead of raw ticks, produced via `DepthResponse::to_decimal`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecimalDepthResponse {
+    pub bids: Vec<(DecimalU64, DecimalU64)>,
+    pub asks: Vec<(DecimalU64, DecimalU64)>,
+    pub resolution: DecimalU64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CommandResponse {
+    PlaceOrder(OrderResponse),
+    CancelOrder(CancelOrderResponse),
+    Depth(DepthResponse),
+    /// The `OrderResponse::Fill` reports produced by a single marketable
+    /// order crossing one or more book levels, in the order they occurred.
+    Fills(Vec<OrderResponse>),
+}
+
+impl CommandResponse {
+    pub fn into_http_response(self) -> Response<String> {
+        match self {
+            CommandResponse::PlaceOrder(response) => response.into_http_response(),
+            CommandResponse::CancelOrder(response) => response.into_http_response(),
+            CommandResponse::Depth(depth) => {
+                let body = serde_json::to_string(&depth).unwrap_or_default();
+
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .body(body)
+                    .expect("status and body are always valid")
+            }
+            CommandResponse::Fills(fills) => {
+                let body = serde_json::to_string(&fills).unwrap_or_default();
+
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .body(body)
+                    .expect("status and body are always valid")
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::http::models::decimal::*;
 use crate::http::models::orders::*;
 use protocol::types::*;
 use serde_json;
@@ -17,6 +18,9 @@ mod tests {
             order_type: OrderType::Limit,
             quantity: 50,
             price: Some(50000),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            time_in_force: TimeInForce::GoodTillCancel,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -39,6 +43,9 @@ mod tests {
             order_type: OrderType::Market,
             quantity: 50,
             price: None, // Market orders don't have price
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            time_in_force: TimeInForce::GoodTillCancel,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -57,6 +64,9 @@ mod tests {
             order_type: OrderType::Limit,
             quantity: 100,
             price: Some(60000),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            time_in_force: TimeInForce::GoodTillCancel,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -75,6 +85,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&ack).unwrap();
@@ -86,11 +97,13 @@ mod tests {
                     order_id: id1,
                     user_id: uid1,
                     symbol: s1,
+                    ..
                 },
                 OrderResponse::Ack {
                     order_id: id2,
                     user_id: uid2,
                     symbol: s2,
+                    ..
                 },
             ) => {
                 assert_eq!(id1, id2);
@@ -108,6 +121,7 @@ mod tests {
             reason: RejectReason::InvalidQuantity,
             symbol: "SOL_USDC".to_string(),
             message: "Quantity must be greater than 0".to_string(),
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&reject).unwrap();
@@ -120,12 +134,14 @@ mod tests {
                     reason: r1,
                     symbol: s1,
                     message: m1,
+                    ..
                 },
                 OrderResponse::Reject {
                     order_id: id2,
                     reason: r2,
                     symbol: s2,
                     message: m2,
+                    ..
                 },
             ) => {
                 assert_eq!(id1, id2);
@@ -143,6 +159,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         };
 
         let http_resp = ack.into_http_response();
@@ -153,6 +170,7 @@ mod tests {
             reason: RejectReason::InvalidQuantity,
             symbol: "SOL_USDC".to_string(),
             message: "Test".to_string(),
+            client_order_id: None,
         };
 
         let http_resp = reject.into_http_response();
@@ -166,7 +184,8 @@ mod tests {
         let request = CancelOrderRequest {
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
-            order_id: 1,
+            order_id: Some(1),
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -185,6 +204,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&ack).unwrap();
@@ -196,11 +216,13 @@ mod tests {
                     order_id: id1,
                     user_id: uid1,
                     symbol: s1,
+                    ..
                 },
                 CancelOrderResponse::Ack {
                     order_id: id2,
                     user_id: uid2,
                     symbol: s2,
+                    ..
                 },
             ) => {
                 assert_eq!(id1, id2);
@@ -217,6 +239,7 @@ mod tests {
             order_id: 1,
             reason: RejectReason::InvalidOrder,
             message: "Order not found".to_string(),
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&reject).unwrap();
@@ -228,11 +251,13 @@ mod tests {
                     order_id: id1,
                     reason: r1,
                     message: m1,
+                    ..
                 },
                 CancelOrderResponse::Reject {
                     order_id: id2,
                     reason: r2,
                     message: m2,
+                    ..
                 },
             ) => {
                 assert_eq!(id1, id2);
@@ -249,6 +274,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         };
 
         let http_resp = ack.into_http_response();
@@ -258,6 +284,7 @@ mod tests {
             order_id: 1,
             reason: RejectReason::InvalidOrder,
             message: "Test".to_string(),
+            client_order_id: None,
         };
 
         let http_resp = reject.into_http_response();
@@ -272,6 +299,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         });
 
         match place_order {
@@ -289,6 +317,7 @@ mod tests {
             reason: RejectReason::InvalidQuantity,
             symbol: "SOL_USDC".to_string(),
             message: "Test".to_string(),
+            client_order_id: None,
         });
 
         match place_order {
@@ -305,6 +334,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         });
 
         match cancel_order {
@@ -321,6 +351,7 @@ mod tests {
             order_id: 1,
             reason: RejectReason::InvalidOrder,
             message: "Test".to_string(),
+            client_order_id: None,
         });
 
         match cancel_order {
@@ -336,6 +367,7 @@ mod tests {
         let depth = CommandResponse::Depth(DepthResponse {
             bids: vec![(49900, 100), (49800, 200)],
             asks: vec![(50100, 50), (50200, 75)],
+            resolution: 0,
         });
 
         match depth {
@@ -356,6 +388,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         });
         let http_resp = place_ack.into_http_response();
         assert_eq!(http_resp.status().as_u16(), 200);
@@ -366,6 +399,7 @@ mod tests {
             reason: RejectReason::InvalidQuantity,
             symbol: "SOL_USDC".to_string(),
             message: "Test".to_string(),
+            client_order_id: None,
         });
         let http_resp = place_reject.into_http_response();
         assert_eq!(http_resp.status().as_u16(), 400);
@@ -375,6 +409,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         });
         let http_resp = cancel_ack.into_http_response();
         assert_eq!(http_resp.status().as_u16(), 200);
@@ -384,6 +419,7 @@ mod tests {
             order_id: 1,
             reason: RejectReason::InvalidOrder,
             message: "Test".to_string(),
+            client_order_id: None,
         });
         let http_resp = cancel_reject.into_http_response();
         assert_eq!(http_resp.status().as_u16(), 400);
@@ -392,6 +428,7 @@ mod tests {
         let depth = CommandResponse::Depth(DepthResponse {
             bids: vec![],
             asks: vec![],
+            resolution: 0,
         });
         let http_resp = depth.into_http_response();
         assert_eq!(http_resp.status().as_u16(), 200);
@@ -403,6 +440,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         });
 
         let json = serde_json::to_string(&place_order).unwrap();
@@ -423,7 +461,13 @@ mod tests {
 
     #[test]
     fn test_depth_query_serialization() {
-        let query = DepthQuery { limit: 20 };
+        let query = DepthQuery {
+            limit: 20,
+            min_price: None,
+            max_price: None,
+            min_quantity: None,
+            aggregate_tick: None,
+        };
 
         let json = serde_json::to_string(&query).unwrap();
         let deserialized: DepthQuery = serde_json::from_str(&json).unwrap();
@@ -434,7 +478,13 @@ mod tests {
     #[test]
     fn test_depth_query_different_limits() {
         for limit in [10, 20, 50, 100] {
-            let query = DepthQuery { limit };
+            let query = DepthQuery {
+                limit,
+                min_price: None,
+                max_price: None,
+                min_quantity: None,
+                aggregate_tick: None,
+            };
             let json = serde_json::to_string(&query).unwrap();
             let deserialized: DepthQuery = serde_json::from_str(&json).unwrap();
             assert_eq!(deserialized.limit, limit);
@@ -448,6 +498,7 @@ mod tests {
         let response = DepthResponse {
             bids: vec![(49900, 100), (49800, 200)],
             asks: vec![(50100, 50), (50200, 75)],
+            resolution: 0,
         };
 
         let json = serde_json::to_string(&response).unwrap();
@@ -464,6 +515,7 @@ mod tests {
         let response = DepthResponse {
             bids: vec![],
             asks: vec![],
+            resolution: 0,
         };
 
         let json = serde_json::to_string(&response).unwrap();
@@ -483,7 +535,11 @@ mod tests {
             asks.push((50000 + i, 100 + i));
         }
 
-        let response = DepthResponse { bids, asks };
+        let response = DepthResponse {
+            bids,
+            asks,
+            resolution: 0,
+        };
 
         let json = serde_json::to_string(&response).unwrap();
         let deserialized: DepthResponse = serde_json::from_str(&json).unwrap();
@@ -503,6 +559,9 @@ mod tests {
             order_type: OrderType::Market,
             quantity: 999,
             price: None,
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            time_in_force: TimeInForce::GoodTillCancel,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&original).unwrap();
@@ -522,6 +581,7 @@ mod tests {
             order_id: 999,
             user_id: 888,
             symbol: "ETH/USD".to_string(),
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&original).unwrap();
@@ -533,11 +593,13 @@ mod tests {
                     order_id: id1,
                     user_id: uid1,
                     symbol: s1,
+                    ..
                 },
                 OrderResponse::Ack {
                     order_id: id2,
                     user_id: uid2,
                     symbol: s2,
+                    ..
                 },
             ) => {
                 assert_eq!(id1, id2);
@@ -559,6 +621,9 @@ mod tests {
             order_type: OrderType::Limit,
             quantity: 0,
             price: Some(50000),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            time_in_force: TimeInForce::GoodTillCancel,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -575,6 +640,9 @@ mod tests {
             order_type: OrderType::Limit,
             quantity: u64::MAX,
             price: Some(u64::MAX),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            time_in_force: TimeInForce::GoodTillCancel,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -602,6 +670,7 @@ mod tests {
                 reason: reason.clone(),
                 symbol: "SOL_USDC".to_string(),
                 message: "Test".to_string(),
+                client_order_id: None,
             };
 
             let json = serde_json::to_string(&reject).unwrap();
@@ -625,6 +694,9 @@ mod tests {
             order_type: OrderType::Limit,
             quantity: 50,
             price: Some(50000),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            time_in_force: TimeInForce::GoodTillCancel,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -641,10 +713,227 @@ mod tests {
             order_type: OrderType::Limit,
             quantity: 50,
             price: Some(50000),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            time_in_force: TimeInForce::GoodTillCancel,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
         let deserialized: OrderRequest = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized.symbol, "BTC/₿");
     }
+
+    // DecimalU64 Tests
+
+    #[test]
+    fn test_decimal_u64_to_decimal_string() {
+        let value = DecimalU64::new(5000000, 5);
+        assert_eq!(value.to_decimal_string(), "50.00000");
+    }
+
+    #[test]
+    fn test_decimal_u64_zero_decimals() {
+        let value = DecimalU64::new(50, 0);
+        assert_eq!(value.to_decimal_string(), "50");
+    }
+
+    #[test]
+    fn test_decimal_u64_round_trip() {
+        let value = DecimalU64::new(5000000, 5);
+        let s = value.to_decimal_string();
+        let parsed = DecimalU64::from_decimal_str(&s).unwrap();
+        assert_eq!(parsed, value);
+    }
+
+    #[test]
+    fn test_decimal_u64_zero_quantity_round_trip() {
+        let value = DecimalU64::new(0, 3);
+        let s = value.to_decimal_string();
+        let parsed = DecimalU64::from_decimal_str(&s).unwrap();
+        assert_eq!(parsed.raw, 0);
+    }
+
+    #[test]
+    fn test_decimal_u64_max_round_trip() {
+        let value = DecimalU64::new(u64::MAX, 0);
+        let s = value.to_decimal_string();
+        let parsed = DecimalU64::from_decimal_str(&s).unwrap();
+        assert_eq!(parsed.raw, u64::MAX);
+    }
+
+    #[test]
+    fn test_decimal_u64_too_many_fractional_digits() {
+        let too_precise = format!("1.{}", "0".repeat(19));
+        let err = DecimalU64::from_decimal_str(&too_precise).unwrap_err();
+        assert_eq!(err, DecimalError::TooManyFractionalDigits(19));
+    }
+
+    #[test]
+    fn test_decimal_u64_overflow() {
+        let err = DecimalU64::from_decimal_str(&format!("{}.1", u64::MAX)).unwrap_err();
+        assert_eq!(err, DecimalError::Overflow);
+    }
+
+    #[test]
+    fn test_decimal_u64_invalid_format() {
+        let err = DecimalU64::from_decimal_str("not-a-number").unwrap_err();
+        assert_eq!(err, DecimalError::InvalidFormat("not-a-number".to_string()));
+    }
+
+    #[test]
+    fn test_decimal_u64_serde_round_trip() {
+        let value = DecimalU64::new(5000000, 5);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, "\"50.00000\"");
+        let deserialized: DecimalU64 = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    // Scale / ScaleRegistry Tests
+
+    #[test]
+    fn test_scale_registry_default_is_raw() {
+        let registry = ScaleRegistry::new();
+        assert_eq!(registry.get("SOL_USDC"), Scale::RAW);
+    }
+
+    #[test]
+    fn test_scale_registry_register_and_get() {
+        let mut registry = ScaleRegistry::new();
+        let scale = Scale {
+            price_decimals: 5,
+            qty_decimals: 3,
+        };
+        registry.register("SOL_USDC", scale);
+        assert_eq!(registry.get("SOL_USDC"), scale);
+    }
+
+    // DecimalOrderRequest / DecimalDepthResponse Tests
+
+    #[test]
+    fn test_decimal_order_request_into_order_request() {
+        let decimal_request = DecimalOrderRequest {
+            user_id: 100,
+            symbol: "SOL_USDC".to_string(),
+            side: Side::Buy,
+            order_type: OrderType::Limit,
+            quantity: DecimalU64::new(50, 0),
+            price: Some(DecimalU64::new(5000000, 5)),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            time_in_force: TimeInForce::GoodTillCancel,
+            client_order_id: None,
+        };
+
+        let request = decimal_request.into_order_request();
+
+        assert_eq!(request.quantity, 50);
+        assert_eq!(request.price, Some(5000000));
+    }
+
+    #[test]
+    fn test_depth_response_to_decimal() {
+        let response = DepthResponse {
+            bids: vec![(5000000, 100)],
+            asks: vec![(5010000, 50)],
+            resolution: 0,
+        };
+
+        let scale = Scale {
+            price_decimals: 5,
+            qty_decimals: 0,
+        };
+        let decimal_response = response.to_decimal(scale);
+
+        assert_eq!(decimal_response.bids[0].0.to_decimal_string(), "50.00000");
+        assert_eq!(decimal_response.asks[0].0.to_decimal_string(), "50.10000");
+    }
+
+    // OrderResponse::Fill Tests
+
+    #[test]
+    fn test_order_response_fill_fully_filled() {
+        let fill = OrderResponse::fill(1, 100, "SOL_USDC".to_string(), 50, 50, Some(5000000), None);
+
+        match fill {
+            OrderResponse::Fill {
+                status,
+                filled_quantity,
+                remaining_quantity,
+                ..
+            } => {
+                assert_eq!(status, OrderStatus::Filled);
+                assert_eq!(filled_quantity, 50);
+                assert_eq!(remaining_quantity, 0);
+                assert_eq!(filled_quantity + remaining_quantity, 50);
+            }
+            _ => panic!("Expected Fill variant"),
+        }
+    }
+
+    #[test]
+    fn test_order_response_fill_partially_filled() {
+        let fill = OrderResponse::fill(1, 100, "SOL_USDC".to_string(), 50, 20, Some(5000000), None);
+
+        match fill {
+            OrderResponse::Fill {
+                status,
+                filled_quantity,
+                remaining_quantity,
+                ..
+            } => {
+                assert_eq!(status, OrderStatus::PartiallyFilled);
+                assert_eq!(filled_quantity, 20);
+                assert_eq!(remaining_quantity, 30);
+                assert_eq!(filled_quantity + remaining_quantity, 50);
+            }
+            _ => panic!("Expected Fill variant"),
+        }
+    }
+
+    #[test]
+    fn test_order_response_fill_unfilled_is_pending() {
+        let fill = OrderResponse::fill(1, 100, "SOL_USDC".to_string(), 50, 0, None, None);
+
+        match fill {
+            OrderResponse::Fill { status, .. } => {
+                assert_eq!(status, OrderStatus::Pending);
+            }
+            _ => panic!("Expected Fill variant"),
+        }
+    }
+
+    #[test]
+    fn test_order_response_fill_into_http_response_is_200() {
+        let fill = OrderResponse::fill(1, 100, "SOL_USDC".to_string(), 50, 50, Some(5000000), None);
+        let http_resp = fill.into_http_response();
+        assert_eq!(http_resp.status().as_u16(), 200);
+    }
+
+    #[test]
+    fn test_order_response_fill_serde_round_trip() {
+        let fill = OrderResponse::fill(
+            1,
+            100,
+            "SOL_USDC".to_string(),
+            50,
+            20,
+            Some(5000000),
+            Some("client-abc".to_string()),
+        );
+
+        let json = serde_json::to_string(&fill).unwrap();
+        let deserialized: OrderResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(fill, deserialized);
+    }
+
+    #[test]
+    fn test_command_response_fills_into_http_response() {
+        let fills = CommandResponse::Fills(vec![
+            OrderResponse::fill(1, 100, "SOL_USDC".to_string(), 50, 20, Some(5000000), None),
+            OrderResponse::fill(1, 100, "SOL_USDC".to_string(), 50, 50, Some(5010000), None),
+        ]);
+
+        let http_resp = fills.into_http_response();
+        assert_eq!(http_resp.status().as_u16(), 200);
+    }
 }
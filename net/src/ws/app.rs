@@ -2,11 +2,8 @@ use redis::Client;
 use std::{net::SocketAddr, sync::Arc};
 use tokio::{net::TcpListener, sync::RwLock, task::JoinHandle};
 
-use crate::ws::broadcasters::{
-    depth::broadcast_depth_events, order_update::broadcast_order_update_events,
-    ticker::broadcast_ticker_events, trade::broadcast_trade_events,
-};
-use crate::ws::{client_manager::UserManager, lib::handle_connection};
+use crate::ws::broadcaster::run_broadcaster;
+use crate::ws::{client_manager::UserManager, lib::handle_connection, types::MarketRegistry};
 
 pub struct WsServerApp {
     pub port: u16,
@@ -27,34 +24,21 @@ impl WsServerApp {
 
         let user_manager = Arc::new(RwLock::new(UserManager::new()));
 
+        let mut registry = MarketRegistry::new();
+        registry.instantiate_market("SOL_USDC");
+        registry.instantiate_market("BTC_USDc");
+        registry.instantiate_market("ETH_USDC");
+        let market_registry = Arc::new(RwLock::new(registry));
+
         let redis_url = "redis://127.0.0.1:6379";
         let redis_client = Client::open(redis_url).expect("[ws] unable to create redis client");
 
-        let trade_user_manager = user_manager.clone();
-        let depth_user_manager = user_manager.clone();
-        let ticker_user_manager = user_manager.clone();
-        let order_update_user_manager = user_manager.clone();
+        let broadcaster_user_manager = user_manager.clone();
 
         let mut broadcaster_handles = Vec::new();
 
-        let redis_trade = redis_client.clone();
-        broadcaster_handles.push(tokio::spawn(async move {
-            let _ = broadcast_trade_events(trade_user_manager, redis_trade).await;
-        }));
-
-        let redis_depth = redis_client.clone();
-        broadcaster_handles.push(tokio::spawn(async move {
-            let _ = broadcast_depth_events(depth_user_manager, redis_depth).await;
-        }));
-
-        let redis_ticker = redis_client.clone();
-        broadcaster_handles.push(tokio::spawn(async move {
-            let _ = broadcast_ticker_events(ticker_user_manager, redis_ticker).await;
-        }));
-
-        let redis_order = redis_client.clone();
         broadcaster_handles.push(tokio::spawn(async move {
-            let _ = broadcast_order_update_events(order_update_user_manager, redis_order).await;
+            run_broadcaster(broadcaster_user_manager, redis_client).await;
         }));
 
         let handle = tokio::spawn(async move {
@@ -65,6 +49,7 @@ impl WsServerApp {
                             stream,
                             user_addr.to_string(),
                             user_manager.clone(),
+                            market_registry.clone(),
                         ));
                     }
                     Err(e) => {
@@ -0,0 +1,137 @@
+use futures_util::StreamExt;
+use redis::Client;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use crate::ws::client_manager::UserManager;
+use crate::ws::decode::{DecodedMessage, RoutingCache, decode};
+use crate::ws::error::BroadcastError;
+
+const PATTERNS: [&str; 6] = [
+    "market:trade:*",
+    "market:depth:*",
+    "market:ticker:*",
+    "market:book_ticker:*",
+    "market:kline:*",
+    "market:order:user:*",
+];
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+// Bounds the number of distinct channels whose routing key `RoutingCache`
+// remembers; well above the handful of symbols/users any one deployment
+// realistically multiplexes over one connection.
+const ROUTING_CACHE_CAPACITY: usize = 4096;
+
+/// Runs every market-data and order-update broadcast off a single shared
+/// Redis pub/sub connection, replacing the four separate tasks (trade,
+/// depth, ticker, order-update) that previously each opened their own
+/// connection and issued their own `psubscribe`. One socket, six
+/// subscriptions (`PATTERNS`), one dispatch loop that routes by channel prefix.
+///
+/// Never returns: a dropped or errored connection is reconnected with
+/// exponential backoff (starting at `INITIAL_BACKOFF`, doubling up to
+/// `MAX_BACKOFF`, reset each time a message's payload is read off the stream)
+/// instead of leaving every subscriber permanently dark. The routing cache
+/// persists across reconnects, since a channel's routing key doesn't change
+/// when the underlying connection does.
+pub async fn run_broadcaster(user_manager: Arc<RwLock<UserManager>>, redis_client: Client) {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut cache = RoutingCache::new(ROUTING_CACHE_CAPACITY);
+
+    loop {
+        if let Err(e) = run_once(&user_manager, &redis_client, &mut backoff, &mut cache).await {
+            eprintln!("[broadcaster] {e}; retrying in {:?}", backoff);
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// One connect-subscribe-dispatch attempt. Returns once the pub/sub stream
+/// ends or a fatal error occurs; `backoff` is reset to `INITIAL_BACKOFF`
+/// after every message whose payload could be read, whether or not it then
+/// decodes to a known route.
+async fn run_once(
+    user_manager: &Arc<RwLock<UserManager>>,
+    redis_client: &Client,
+    backoff: &mut Duration,
+    cache: &mut RoutingCache,
+) -> Result<(), BroadcastError> {
+    let (mut sink, mut stream) = redis_client.get_async_pubsub().await?.split();
+
+    for pattern in PATTERNS {
+        sink.psubscribe(pattern)
+            .await
+            .map_err(|source| BroadcastError::SubscribeFailed { pattern, source })?;
+    }
+
+    while let Some(msg) = stream.next().await {
+        let channel: String = msg.get_channel().unwrap_or_default();
+        let payload: String = match msg.get_payload() {
+            Ok(payload) => payload,
+            Err(e) => {
+                let err = BroadcastError::PayloadDecodeFailed {
+                    channel: channel.clone(),
+                    reason: e.to_string(),
+                };
+                eprintln!("[broadcaster] {err}");
+                continue;
+            }
+        };
+
+        match decode(cache, &channel, &payload) {
+            Ok(Some(decoded)) => dispatch(user_manager, decoded).await,
+            Ok(None) => eprintln!("[broadcaster] unrecognized channel: {}", channel),
+            Err(e) => eprintln!("[broadcaster] {e}"),
+        }
+
+        *backoff = INITIAL_BACKOFF;
+    }
+
+    Ok(())
+}
+
+/// Routes one decoded message to the `UserManager` method for its kind.
+async fn dispatch(user_manager: &Arc<RwLock<UserManager>>, decoded: DecodedMessage) {
+    let mut manager = user_manager.write().await;
+    match decoded {
+        DecodedMessage::Trade {
+            symbol,
+            stream,
+            payload,
+            frame,
+        } => manager.broadcast_trade(&symbol, &stream, &payload, frame),
+        DecodedMessage::Depth {
+            symbol,
+            stream,
+            payload,
+            frame,
+        } => manager.broadcast_depth(&symbol, &stream, &payload, frame),
+        DecodedMessage::Ticker {
+            symbol,
+            stream,
+            payload,
+            frame,
+        } => manager.broadcast_ticker(&symbol, &stream, &payload, frame),
+        DecodedMessage::BookTicker {
+            symbol,
+            stream,
+            payload,
+            frame,
+        } => manager.broadcast_book_ticker(&symbol, &stream, &payload, frame),
+        DecodedMessage::Kline {
+            symbol,
+            interval,
+            stream,
+            payload,
+            frame,
+        } => manager.broadcast_kline(&symbol, &interval, &stream, &payload, frame),
+        DecodedMessage::OrderUpdate { user_id, frame } => {
+            manager.send_order_update(user_id, frame)
+        }
+    }
+}
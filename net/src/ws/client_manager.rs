@@ -1,19 +1,51 @@
 use futures_util::{SinkExt, stream::SplitSink};
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tokio::net::TcpStream;
+use tokio::sync::{
+    RwLock,
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+};
 use tokio_tungstenite::{WebSocketStream, tungstenite::Message};
 
 pub struct UserInfo {
     pub user_id: Option<u64>,
-    pub writer: SplitSink<WebSocketStream<TcpStream>, Message>,
+    pub writer: UnboundedSender<Message>,
     pub subscribed_trades: HashSet<String>,
     pub subscribed_tickers: HashSet<String>,
+    pub subscribed_book_tickers: HashSet<String>,
     pub subscribed_depth: HashSet<String>,
+    // Keyed by (symbol, interval), since a kline subscription is scoped to
+    // both, unlike the single-dimension trade/ticker/depth subscriptions.
+    pub subscribed_klines: HashSet<(String, String)>,
+    // Set once this connection subscribes via the combined/multistream
+    // `params` form, so every public-stream event it receives afterward is
+    // wrapped as `{ "stream": "<symbol>@<event>", "data": { ... } }` instead
+    // of sent bare, letting it demultiplex many streams over one socket.
+    pub combined: bool,
 }
 
 pub struct UserManager {
     pub users: HashMap<String, UserInfo>,
     pub user_map: HashMap<u64, String>,
+
+    // Reverse symbol -> subscriber indices, kept in sync by the matching
+    // subscribe_*/unsubscribe_*/remove_user calls, so broadcasting touches
+    // only the users actually interested in a symbol instead of scanning
+    // every connected user.
+    trade_subscribers: HashMap<String, HashSet<String>>,
+    ticker_subscribers: HashMap<String, HashSet<String>>,
+    book_ticker_subscribers: HashMap<String, HashSet<String>>,
+    depth_subscribers: HashMap<String, HashSet<String>>,
+    kline_subscribers: HashMap<(String, String), HashSet<String>>,
+
+    // Last depth update broadcast per symbol (update id, stream tag, raw
+    // payload), so a freshly subscribing connection can be sent a baseline
+    // snapshot immediately instead of waiting on the next broadcast diff to
+    // reconstruct book state from nothing. Kept as the raw parts rather than
+    // a built `Message` so the snapshot can still be wrapped for a
+    // combined-mode subscriber.
+    last_depth: HashMap<String, (u64, String, String)>,
 }
 
 impl UserManager {
@@ -21,14 +53,28 @@ impl UserManager {
         Self {
             users: HashMap::new(),
             user_map: HashMap::new(),
+            trade_subscribers: HashMap::new(),
+            ticker_subscribers: HashMap::new(),
+            book_ticker_subscribers: HashMap::new(),
+            depth_subscribers: HashMap::new(),
+            kline_subscribers: HashMap::new(),
+            last_depth: HashMap::new(),
         }
     }
 
+    /// Registers `user_addr` and spawns a writer task that owns `socket_writer`
+    /// and drains an unbounded channel into it, so broadcasting to this user
+    /// is a cheap, non-blocking push rather than an `await` held under the
+    /// manager's lock. `user_manager` lets the writer task remove this user
+    /// once the channel closes or a write to the socket fails.
     pub fn add_user(
         &mut self,
         user_addr: &str,
-        writer: SplitSink<WebSocketStream<TcpStream>, Message>,
+        socket_writer: SplitSink<WebSocketStream<TcpStream>, Message>,
+        user_manager: Arc<RwLock<UserManager>>,
     ) {
+        let (writer, rx) = mpsc::unbounded_channel();
+
         self.users.insert(
             user_addr.to_string(),
             UserInfo {
@@ -36,15 +82,41 @@ impl UserManager {
                 writer,
                 subscribed_trades: HashSet::new(),
                 subscribed_tickers: HashSet::new(),
+                subscribed_book_tickers: HashSet::new(),
                 subscribed_depth: HashSet::new(),
+                subscribed_klines: HashSet::new(),
+                combined: false,
             },
         );
 
+        tokio::spawn(run_writer(
+            user_addr.to_string(),
+            socket_writer,
+            rx,
+            user_manager,
+        ));
+
         println!("[UserManager] New WS user added: {}", user_addr);
     }
 
     pub fn remove_user(&mut self, user_addr: &str) {
         if let Some(user) = self.users.remove(user_addr) {
+            for symbol in &user.subscribed_trades {
+                remove_subscriber(&mut self.trade_subscribers, symbol, user_addr);
+            }
+            for symbol in &user.subscribed_tickers {
+                remove_subscriber(&mut self.ticker_subscribers, symbol, user_addr);
+            }
+            for symbol in &user.subscribed_book_tickers {
+                remove_subscriber(&mut self.book_ticker_subscribers, symbol, user_addr);
+            }
+            for symbol in &user.subscribed_depth {
+                remove_subscriber(&mut self.depth_subscribers, symbol, user_addr);
+            }
+            for key in &user.subscribed_klines {
+                remove_kline_subscriber(&mut self.kline_subscribers, key, user_addr);
+            }
+
             if let Some(uid) = user.user_id {
                 self.user_map.remove(&uid);
                 println!("[UserManager] WS user removed: {}", user_addr);
@@ -84,12 +156,22 @@ impl UserManager {
         }
     }
 
-    pub async fn send_order_update(&mut self, user_id: u64, order_update: &str) {
+    /// Switches `user_addr` into combined/multistream mode: every public
+    /// event it's subscribed to from now on arrives wrapped as
+    /// `{ "stream": "<symbol>@<event>", "data": { ... } }` rather than bare.
+    pub fn set_combined(&mut self, user_addr: &str) {
+        if let Some(user) = self.users.get_mut(user_addr) {
+            user.combined = true;
+        } else {
+            println!("[UserManager] User not found: {}", user_addr);
+        }
+    }
+
+    pub fn send_order_update(&mut self, user_id: u64, frame: Message) {
         if let Some(user_addr) = self.user_map.get(&user_id) {
             if let Some(user) = self.users.get_mut(user_addr) {
-                let message = Message::text(order_update);
-                if let Err(e) = user.writer.send(message).await {
-                    eprintln!("Could not send order update, error occured: {}", e);
+                if let Err(e) = user.writer.send(frame) {
+                    eprintln!("Could not queue order update, error occured: {}", e);
                 }
             } else {
                 println!("[UserManager] User not found: {}", user_addr);
@@ -100,10 +182,90 @@ impl UserManager {
     }
 }
 
+/// Drains `rx` into `socket_writer` until the channel closes (every sender
+/// dropped, i.e. the user was removed) or a write fails (the client is
+/// gone), then removes `user_addr` from `user_manager` so a failed or
+/// abandoned connection doesn't linger as a dead subscriber.
+async fn run_writer(
+    user_addr: String,
+    mut socket_writer: SplitSink<WebSocketStream<TcpStream>, Message>,
+    mut rx: UnboundedReceiver<Message>,
+    user_manager: Arc<RwLock<UserManager>>,
+) {
+    while let Some(message) = rx.recv().await {
+        if let Err(e) = socket_writer.send(message).await {
+            eprintln!("[UserManager] write failed for {}, dropping: {}", user_addr, e);
+            break;
+        }
+    }
+
+    user_manager.write().await.remove_user(&user_addr);
+}
+
+/// Removes `user_addr` from `symbol`'s subscriber set, dropping the set
+/// entirely once it's empty so stale symbols don't accumulate in the index.
+fn remove_subscriber(index: &mut HashMap<String, HashSet<String>>, symbol: &str, user_addr: &str) {
+    if let Some(subscribers) = index.get_mut(symbol) {
+        subscribers.remove(user_addr);
+        if subscribers.is_empty() {
+            index.remove(symbol);
+        }
+    }
+}
+
+/// Same as `remove_subscriber`, but for the `(symbol, interval)`-keyed kline
+/// index.
+fn remove_kline_subscriber(
+    index: &mut HashMap<(String, String), HashSet<String>>,
+    key: &(String, String),
+    user_addr: &str,
+) {
+    if let Some(subscribers) = index.get_mut(key) {
+        subscribers.remove(user_addr);
+        if subscribers.is_empty() {
+            index.remove(key);
+        }
+    }
+}
+
+/// Wraps `payload` (raw event JSON) as a combined/multistream frame:
+/// `{ "stream": "<stream>", "data": <payload> }`. `payload` is passed
+/// through as already-valid JSON rather than re-parsed, mirroring how
+/// `decode::decode` never parses it either.
+fn wrap_stream(stream: &str, payload: &str) -> Message {
+    Message::text(format!(r#"{{"stream":"{}","data":{}}}"#, stream, payload))
+}
+
+/// Sends `frame` to `user_addr`, substituting `wrap_stream(stream, payload)`
+/// if the user is in combined mode.
+fn send_public(
+    users: &mut HashMap<String, UserInfo>,
+    user_addr: &str,
+    stream: &str,
+    payload: &str,
+    frame: &Message,
+    kind: &str,
+) {
+    if let Some(user) = users.get_mut(user_addr) {
+        let outgoing = if user.combined {
+            wrap_stream(stream, payload)
+        } else {
+            frame.clone()
+        };
+        if let Err(e) = user.writer.send(outgoing) {
+            eprintln!("Could not queue {}, error occured: {}", kind, e);
+        }
+    }
+}
+
 impl UserManager {
     pub fn subscribe_trade(&mut self, user_addr: &str, symbol: &str) {
         if let Some(user) = self.users.get_mut(user_addr) {
             user.subscribed_trades.insert(symbol.to_string());
+            self.trade_subscribers
+                .entry(symbol.to_string())
+                .or_default()
+                .insert(user_addr.to_string());
             println!(
                 "[UserManager] User subscribed to trade: {} -> {}",
                 user_addr, symbol
@@ -116,6 +278,7 @@ impl UserManager {
     pub fn unsubscribe_trade(&mut self, user_addr: &str, symbol: &str) {
         if let Some(user) = self.users.get_mut(user_addr) {
             user.subscribed_trades.remove(symbol);
+            remove_subscriber(&mut self.trade_subscribers, symbol, user_addr);
             println!(
                 "[UserManager] User unsubscribed from trade: {} -> {}",
                 user_addr, symbol
@@ -125,14 +288,13 @@ impl UserManager {
         }
     }
 
-    pub async fn broadcast_trade(&mut self, symbol: &str, trade: &str) {
-        for user in self.users.values_mut() {
-            if user.subscribed_trades.contains(symbol) {
-                let message = Message::text(trade);
-                if let Err(e) = user.writer.send(message).await {
-                    eprintln!("Could not send trade, error occured: {}", e);
-                }
-            }
+    pub fn broadcast_trade(&mut self, symbol: &str, stream: &str, payload: &str, frame: Message) {
+        let Some(subscribers) = self.trade_subscribers.get(symbol) else {
+            return;
+        };
+
+        for user_addr in subscribers {
+            send_public(&mut self.users, user_addr, stream, payload, &frame, "trade");
         }
     }
 }
@@ -141,6 +303,10 @@ impl UserManager {
     pub fn subscribe_ticker(&mut self, user_addr: &str, symbol: &str) {
         if let Some(user) = self.users.get_mut(user_addr) {
             user.subscribed_tickers.insert(symbol.to_string());
+            self.ticker_subscribers
+                .entry(symbol.to_string())
+                .or_default()
+                .insert(user_addr.to_string());
             println!(
                 "[UserManager] User subscribed to ticker: {} -> {}",
                 user_addr, symbol
@@ -153,6 +319,7 @@ impl UserManager {
     pub fn unsubscribe_ticker(&mut self, user_addr: &str, symbol: &str) {
         if let Some(user) = self.users.get_mut(user_addr) {
             user.subscribed_tickers.remove(symbol);
+            remove_subscriber(&mut self.ticker_subscribers, symbol, user_addr);
             println!(
                 "[UserManager] User unsubscribed from ticker: {} -> {}",
                 user_addr, symbol
@@ -162,22 +329,95 @@ impl UserManager {
         }
     }
 
-    pub async fn broadcast_ticker(&mut self, symbol: &str, ticker: &str) {
-        for user in self.users.values_mut() {
-            if user.subscribed_tickers.contains(symbol) {
-                let message = Message::text(ticker);
-                if let Err(e) = user.writer.send(message).await {
-                    eprintln!("Could not send ticker, error occured: {}", e);
-                }
-            }
+    pub fn broadcast_ticker(&mut self, symbol: &str, stream: &str, payload: &str, frame: Message) {
+        let Some(subscribers) = self.ticker_subscribers.get(symbol) else {
+            return;
+        };
+
+        for user_addr in subscribers {
+            send_public(&mut self.users, user_addr, stream, payload, &frame, "ticker");
+        }
+    }
+}
+
+impl UserManager {
+    pub fn subscribe_book_ticker(&mut self, user_addr: &str, symbol: &str) {
+        if let Some(user) = self.users.get_mut(user_addr) {
+            user.subscribed_book_tickers.insert(symbol.to_string());
+            self.book_ticker_subscribers
+                .entry(symbol.to_string())
+                .or_default()
+                .insert(user_addr.to_string());
+            println!(
+                "[UserManager] User subscribed to book ticker: {} -> {}",
+                user_addr, symbol
+            );
+        } else {
+            println!("[UserManager] User not found: {}", user_addr);
+        }
+    }
+
+    pub fn unsubscribe_book_ticker(&mut self, user_addr: &str, symbol: &str) {
+        if let Some(user) = self.users.get_mut(user_addr) {
+            user.subscribed_book_tickers.remove(symbol);
+            remove_subscriber(&mut self.book_ticker_subscribers, symbol, user_addr);
+            println!(
+                "[UserManager] User unsubscribed from book ticker: {} -> {}",
+                user_addr, symbol
+            );
+        } else {
+            println!("[UserManager] User not found: {}", user_addr);
+        }
+    }
+
+    pub fn broadcast_book_ticker(
+        &mut self,
+        symbol: &str,
+        stream: &str,
+        payload: &str,
+        frame: Message,
+    ) {
+        let Some(subscribers) = self.book_ticker_subscribers.get(symbol) else {
+            return;
+        };
+
+        for user_addr in subscribers {
+            send_public(&mut self.users, user_addr, stream, payload, &frame, "book ticker");
         }
     }
 }
 
 impl UserManager {
+    /// Subscribes `user_addr` to `symbol`'s depth stream. If a depth frame
+    /// has already been broadcast for `symbol`, it's pushed directly to this
+    /// connection first (update id included) so the client has a baseline
+    /// book to apply subsequent diffs onto before it joins the broadcast
+    /// set and starts receiving them.
     pub fn subscribe_depth(&mut self, user_addr: &str, symbol: &str) {
+        let snapshot = self.last_depth.get(symbol).cloned();
+
         if let Some(user) = self.users.get_mut(user_addr) {
+            if let Some((update_id, stream, payload)) = snapshot {
+                let frame = if user.combined {
+                    wrap_stream(&stream, &payload)
+                } else {
+                    Message::text(payload)
+                };
+                if let Err(e) = user.writer.send(frame) {
+                    eprintln!("Could not queue depth snapshot, error occured: {}", e);
+                } else {
+                    println!(
+                        "[UserManager] Sent depth snapshot to {} -> {} (update_id {})",
+                        user_addr, symbol, update_id
+                    );
+                }
+            }
+
             user.subscribed_depth.insert(symbol.to_string());
+            self.depth_subscribers
+                .entry(symbol.to_string())
+                .or_default()
+                .insert(user_addr.to_string());
             println!(
                 "[UserManager] User subscribed to depth: {} -> {}",
                 user_addr, symbol
@@ -192,6 +432,7 @@ impl UserManager {
     pub fn unsubscribe_depth(&mut self, user_addr: &str, symbol: &str) {
         if let Some(user) = self.users.get_mut(user_addr) {
             user.subscribed_depth.remove(symbol);
+            remove_subscriber(&mut self.depth_subscribers, symbol, user_addr);
             println!(
                 "[UserManager] User unsubscribed from depth: {} -> {}",
                 user_addr, symbol
@@ -201,14 +442,73 @@ impl UserManager {
         }
     }
 
-    pub async fn broadcast_depth(&mut self, symbol: &str, depth: &str) {
-        for user in self.users.values_mut() {
-            if user.subscribed_depth.contains(symbol) {
-                let message = Message::text(depth);
-                if let Err(e) = user.writer.send(message).await {
-                    eprintln!("Could not send depth, error occured: {}", e);
-                }
-            }
+    pub fn broadcast_depth(&mut self, symbol: &str, stream: &str, payload: &str, frame: Message) {
+        let update_id = match self.last_depth.get(symbol) {
+            Some((id, _, _)) => id + 1,
+            None => 1,
+        };
+        self.last_depth.insert(
+            symbol.to_string(),
+            (update_id, stream.to_string(), payload.to_string()),
+        );
+
+        let Some(subscribers) = self.depth_subscribers.get(symbol) else {
+            return;
+        };
+
+        for user_addr in subscribers {
+            send_public(&mut self.users, user_addr, stream, payload, &frame, "depth");
+        }
+    }
+}
+
+impl UserManager {
+    pub fn subscribe_kline(&mut self, user_addr: &str, symbol: &str, interval: &str) {
+        if let Some(user) = self.users.get_mut(user_addr) {
+            let key = (symbol.to_string(), interval.to_string());
+            user.subscribed_klines.insert(key.clone());
+            self.kline_subscribers
+                .entry(key)
+                .or_default()
+                .insert(user_addr.to_string());
+            println!(
+                "[UserManager] User subscribed to kline: {} -> {} {}",
+                user_addr, symbol, interval
+            );
+        } else {
+            println!("[UserManager] User not found: {}", user_addr);
+        }
+    }
+
+    pub fn unsubscribe_kline(&mut self, user_addr: &str, symbol: &str, interval: &str) {
+        if let Some(user) = self.users.get_mut(user_addr) {
+            let key = (symbol.to_string(), interval.to_string());
+            user.subscribed_klines.remove(&key);
+            remove_kline_subscriber(&mut self.kline_subscribers, &key, user_addr);
+            println!(
+                "[UserManager] User unsubscribed from kline: {} -> {} {}",
+                user_addr, symbol, interval
+            );
+        } else {
+            println!("[UserManager] User not found: {}", user_addr);
+        }
+    }
+
+    pub fn broadcast_kline(
+        &mut self,
+        symbol: &str,
+        interval: &str,
+        stream: &str,
+        payload: &str,
+        frame: Message,
+    ) {
+        let key = (symbol.to_string(), interval.to_string());
+        let Some(subscribers) = self.kline_subscribers.get(&key) else {
+            return;
+        };
+
+        for user_addr in subscribers {
+            send_public(&mut self.users, user_addr, stream, payload, &frame, "kline");
         }
     }
 }
@@ -0,0 +1,196 @@
+use std::collections::{HashMap, VecDeque};
+
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::ws::error::BroadcastError;
+
+/// A payload parsed once off a Redis pub/sub channel, carrying the routing
+/// key (symbol or user_id) `broadcaster::dispatch` needs plus the outbound
+/// WS frame, already built, so fanning it out to N subscribers clones a
+/// `Message` instead of re-running `Message::text` N times. Every public
+/// (symbol-scoped) variant also carries `stream`, its Binance-style
+/// `symbol@event` tag (e.g. `"btcusdc@trade"`, `"btcusdc@kline_1m"`), and
+/// `payload`, the raw unwrapped JSON text, so `UserManager` can build the
+/// `{ "stream", "data" }` combined-mode frame once per broadcast instead of
+/// per subscriber.
+pub enum DecodedMessage {
+    Trade {
+        symbol: String,
+        stream: String,
+        payload: String,
+        frame: Message,
+    },
+    Depth {
+        symbol: String,
+        stream: String,
+        payload: String,
+        frame: Message,
+    },
+    Ticker {
+        symbol: String,
+        stream: String,
+        payload: String,
+        frame: Message,
+    },
+    BookTicker {
+        symbol: String,
+        stream: String,
+        payload: String,
+        frame: Message,
+    },
+    Kline {
+        symbol: String,
+        interval: String,
+        stream: String,
+        payload: String,
+        frame: Message,
+    },
+    OrderUpdate { user_id: u64, frame: Message },
+}
+
+/// The routing key a channel string decodes to, cached by `RoutingCache` so
+/// a hot channel skips re-splitting it on every message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RoutingKey {
+    Trade(String),
+    Depth(String),
+    Ticker(String),
+    BookTicker(String),
+    Kline(String, String),
+    OrderUpdate(u64),
+}
+
+/// Decodes one `(channel, payload)` pub/sub message into a `DecodedMessage`,
+/// consulting `cache` for the channel's routing key before falling back to
+/// splitting the channel string. Returns `Ok(None)` for a channel outside
+/// the six subscribed prefixes (defensive; `psubscribe` shouldn't produce
+/// one) and `Err` only for a channel this gateway should understand but
+/// can't route, e.g. a non-numeric order-update user_id.
+pub fn decode(
+    cache: &mut RoutingCache,
+    channel: &str,
+    payload: &str,
+) -> Result<Option<DecodedMessage>, BroadcastError> {
+    let key = match cache.get(channel) {
+        Some(key) => key,
+        None => {
+            let Some(key) = parse_routing_key(channel)? else {
+                return Ok(None);
+            };
+            cache.insert(channel.to_string(), key.clone());
+            key
+        }
+    };
+
+    let frame = Message::text(payload.to_string());
+    Ok(Some(match key {
+        RoutingKey::Trade(symbol) => DecodedMessage::Trade {
+            stream: format!("{}@trade", symbol.to_lowercase()),
+            symbol,
+            payload: payload.to_string(),
+            frame,
+        },
+        RoutingKey::Depth(symbol) => DecodedMessage::Depth {
+            stream: format!("{}@depth", symbol.to_lowercase()),
+            symbol,
+            payload: payload.to_string(),
+            frame,
+        },
+        RoutingKey::Ticker(symbol) => DecodedMessage::Ticker {
+            stream: format!("{}@ticker", symbol.to_lowercase()),
+            symbol,
+            payload: payload.to_string(),
+            frame,
+        },
+        RoutingKey::BookTicker(symbol) => DecodedMessage::BookTicker {
+            stream: format!("{}@bookTicker", symbol.to_lowercase()),
+            symbol,
+            payload: payload.to_string(),
+            frame,
+        },
+        RoutingKey::Kline(symbol, interval) => DecodedMessage::Kline {
+            stream: format!("{}@kline_{}", symbol.to_lowercase(), interval.to_lowercase()),
+            symbol,
+            interval,
+            payload: payload.to_string(),
+            frame,
+        },
+        RoutingKey::OrderUpdate(user_id) => DecodedMessage::OrderUpdate { user_id, frame },
+    }))
+}
+
+fn parse_routing_key(channel: &str) -> Result<Option<RoutingKey>, BroadcastError> {
+    let mut parts = channel.split(':');
+    parts.next(); // "market"
+
+    match parts.next() {
+        Some("trade") => Ok(Some(RoutingKey::Trade(
+            parts.next().unwrap_or_default().to_string(),
+        ))),
+        Some("depth") => Ok(Some(RoutingKey::Depth(
+            parts.next().unwrap_or_default().to_string(),
+        ))),
+        Some("ticker") => Ok(Some(RoutingKey::Ticker(
+            parts.next().unwrap_or_default().to_string(),
+        ))),
+        Some("book_ticker") => Ok(Some(RoutingKey::BookTicker(
+            parts.next().unwrap_or_default().to_string(),
+        ))),
+        Some("kline") => Ok(Some(RoutingKey::Kline(
+            parts.next().unwrap_or_default().to_string(),
+            parts.next().unwrap_or_default().to_string(),
+        ))),
+        Some("order") => {
+            parts.next(); // "user"
+            parts
+                .next()
+                .and_then(|id| id.parse::<u64>().ok())
+                .map(|id| Ok(Some(RoutingKey::OrderUpdate(id))))
+                .unwrap_or_else(|| Err(BroadcastError::InvalidUserId(channel.to_string())))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Caches the `RoutingKey` a channel string decodes to, bounded at
+/// `capacity` entries and evicting the least-recently-used one once full, so
+/// a popular symbol's channel skips re-parsing on every message it emits.
+pub struct RoutingCache {
+    capacity: usize,
+    entries: HashMap<String, RoutingKey>,
+    // Most-recently-used channel is at the back; eviction pops the front.
+    recency: VecDeque<String>,
+}
+
+impl RoutingCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: VecDeque::new(),
+        }
+    }
+
+    fn get(&mut self, channel: &str) -> Option<RoutingKey> {
+        let key = self.entries.get(channel).cloned()?;
+        self.touch(channel);
+        Some(key)
+    }
+
+    fn insert(&mut self, channel: String, key: RoutingKey) {
+        if !self.entries.contains_key(&channel) && self.entries.len() >= self.capacity {
+            if let Some(evicted) = self.recency.pop_front() {
+                self.entries.remove(&evicted);
+            }
+        }
+        self.entries.insert(channel.clone(), key);
+        self.touch(&channel);
+    }
+
+    fn touch(&mut self, channel: &str) {
+        if let Some(pos) = self.recency.iter().position(|c| c == channel) {
+            self.recency.remove(pos);
+        }
+        self.recency.push_back(channel.to_string());
+    }
+}
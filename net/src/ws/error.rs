@@ -0,0 +1,22 @@
+use thiserror::Error;
+
+/// Failure classes the broadcaster's supervision loop can observe, so a
+/// dropped Redis connection (worth reconnecting) is distinguished from a bad
+/// subscribe call, an undecodable payload, or a malformed routing key.
+#[derive(Debug, Error)]
+pub enum BroadcastError {
+    #[error("redis pub/sub connection lost: {0}")]
+    ConnectionLost(#[from] redis::RedisError),
+
+    #[error("failed to subscribe to {pattern}: {source}")]
+    SubscribeFailed {
+        pattern: &'static str,
+        source: redis::RedisError,
+    },
+
+    #[error("could not decode payload on channel {channel}: {reason}")]
+    PayloadDecodeFailed { channel: String, reason: String },
+
+    #[error("invalid user_id in channel: {0}")]
+    InvalidUserId(String),
+}
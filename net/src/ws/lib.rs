@@ -1,18 +1,27 @@
 use futures_util::StreamExt;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpStream;
 use tokio::sync::RwLock;
+use tokio::time::Instant;
 use tokio_tungstenite::{WebSocketStream, accept_async, tungstenite::Message};
 
 use crate::ws::{
     client_manager::UserManager,
-    types::{Event, Method, WsClientMessage},
+    types::{Event, MarketRegistry, Method, WsClientMessage, parse_stream_token},
 };
 
+// How often a `Ping` is sent to every connection, and how long a connection
+// may go without any inbound frame (a reply `Pong`, or anything else) before
+// it's considered half-open and dropped.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+const IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
 pub async fn handle_connection(
     stream: TcpStream,
     user_addr: String,
     user_manager: Arc<RwLock<UserManager>>,
+    market_registry: Arc<RwLock<MarketRegistry>>,
 ) {
     let ws_stream = match accept_async(stream).await {
         Ok(ws) => ws,
@@ -24,57 +33,106 @@ pub async fn handle_connection(
 
     println!("[ws] connection established from {}", user_addr);
 
-    handle_stream(ws_stream, &user_addr, user_manager.clone()).await;
+    handle_stream(ws_stream, &user_addr, user_manager.clone(), market_registry).await;
 }
 
 pub async fn handle_stream(
     ws_stream: WebSocketStream<TcpStream>,
     user_addr: &str,
     user_manager: Arc<RwLock<UserManager>>,
+    market_registry: Arc<RwLock<MarketRegistry>>,
 ) {
     let (write, mut read) = ws_stream.split();
 
-    {
+    let writer = {
         let mut manager = user_manager.write().await;
-        manager.add_user(user_addr, write);
+        manager.add_user(user_addr, write, user_manager.clone());
         println!("WebSocket connection established from: {}", user_addr);
-    }
+        manager.users.get(user_addr).map(|u| u.writer.clone())
+    };
 
-    while let Some(msg) = read.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                println!("[ws] received message: {}", text);
-                let parsed: Result<WsClientMessage, _> = serde_json::from_str(&text);
-                match parsed {
-                    Ok(parsed) => {
-                        handle_message(parsed, &user_addr, user_manager.clone()).await;
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut last_seen = Instant::now();
+
+    loop {
+        tokio::select! {
+            msg = read.next() => {
+                let Some(msg) = msg else {
+                    break;
+                };
+                last_seen = Instant::now();
+
+                match msg {
+                    Ok(Message::Text(text)) => {
+                        println!("[ws] received message: {}", text);
+                        let parsed: Result<WsClientMessage, _> = serde_json::from_str(&text);
+                        match parsed {
+                            Ok(parsed) => {
+                                handle_message(
+                                    parsed,
+                                    &user_addr,
+                                    user_manager.clone(),
+                                    market_registry.clone(),
+                                )
+                                .await;
+                            }
+                            Err(e) => {
+                                eprintln!("[ws] error parsing message: {}", e);
+                            }
+                        }
                     }
-                    Err(e) => {
-                        eprintln!("[ws] error parsing message: {}", e);
+
+                    Ok(Message::Binary(bin)) => {
+                        println!("[ws] received binary message: {}", bin.len());
                     }
-                }
-            }
 
-            Ok(Message::Binary(bin)) => {
-                println!("[ws] received binary message: {}", bin.len());
-            }
+                    Ok(Message::Ping(ping)) => {
+                        println!("[ws] received ping: {:?}", ping);
+                        if let Some(writer) = &writer {
+                            if let Err(e) = writer.send(Message::Pong(ping)) {
+                                eprintln!("[ws] failed to queue pong for {}: {}", user_addr, e);
+                            }
+                        }
+                    }
 
-            Ok(Message::Ping(ping)) => {
-                println!("[ws] received ping: {:?}", ping);
-            }
+                    Ok(Message::Pong(pong)) => {
+                        println!("[ws] received pong: {:?}", pong);
+                    }
 
-            Ok(Message::Close(close)) => {
-                println!("[ws] received close: {:?}", close);
-                let mut manager = user_manager.write().await;
-                manager.remove_user(user_addr);
-                println!("WebSocket connection closed from: {}", user_addr);
-            }
+                    Ok(Message::Close(close)) => {
+                        println!("[ws] received close: {:?}", close);
+                        let mut manager = user_manager.write().await;
+                        manager.remove_user(user_addr);
+                        println!("WebSocket connection closed from: {}", user_addr);
+                        break;
+                    }
 
-            Err(e) => {
-                eprintln!("[ws] read error from {}: {}", user_addr, e);
+                    Err(e) => {
+                        eprintln!("[ws] read error from {}: {}", user_addr, e);
+                    }
+
+                    _ => {}
+                }
             }
 
-            _ => {}
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > IDLE_TIMEOUT {
+                    eprintln!(
+                        "[ws] {} idle for {:?}, dropping connection",
+                        user_addr,
+                        last_seen.elapsed()
+                    );
+                    user_manager.write().await.remove_user(user_addr);
+                    break;
+                }
+
+                if let Some(writer) = &writer {
+                    if let Err(e) = writer.send(Message::Ping(Default::default())) {
+                        eprintln!("[ws] failed to queue heartbeat ping for {}: {}", user_addr, e);
+                        break;
+                    }
+                }
+            }
         }
     }
 }
@@ -83,56 +141,159 @@ async fn handle_message(
     msg: WsClientMessage,
     user_addr: &str,
     user_manager: Arc<RwLock<UserManager>>,
+    market_registry: Arc<RwLock<MarketRegistry>>,
+) {
+    // Combined/multistream form: fan each `SYMBOL@event` token in `params`
+    // out to its own subscribe/unsubscribe, and switch this connection into
+    // combined mode so the events it receives come back stream-tagged.
+    if let Some(tokens) = &msg.params {
+        if matches!(msg.method, Method::SUBSCRIBE) {
+            user_manager.write().await.set_combined(user_addr);
+        }
+
+        for token in tokens {
+            let Some((symbol, event, interval)) = parse_stream_token(token) else {
+                eprintln!(
+                    "[ws] rejecting unrecognized stream token from {}: {}",
+                    user_addr, token
+                );
+                continue;
+            };
+            dispatch_one(
+                event,
+                symbol,
+                interval,
+                msg.method,
+                msg.user_id,
+                user_addr,
+                &user_manager,
+                &market_registry,
+            )
+            .await;
+        }
+        return;
+    }
+
+    let Some(event) = msg.event.clone() else {
+        eprintln!("[ws] rejecting message from {} with no event", user_addr);
+        return;
+    };
+    let Some(symbol) = msg.symbol.clone() else {
+        eprintln!("[ws] rejecting message from {} with no symbol", user_addr);
+        return;
+    };
+
+    dispatch_one(
+        event,
+        symbol,
+        msg.interval,
+        msg.method,
+        msg.user_id,
+        user_addr,
+        &user_manager,
+        &market_registry,
+    )
+    .await;
+}
+
+/// Subscribes or unsubscribes one `(event, symbol)` stream for `user_addr`,
+/// shared by the single-stream `event`/`symbol` message form and each token
+/// fanned out of a combined-mode `params` subscribe.
+async fn dispatch_one(
+    event: Event,
+    symbol: String,
+    interval: Option<String>,
+    method: Method,
+    user_id: Option<u64>,
+    user_addr: &str,
+    user_manager: &Arc<RwLock<UserManager>>,
+    market_registry: &Arc<RwLock<MarketRegistry>>,
 ) {
-    match msg.event {
-        Event::TRADE => match msg.method {
+    // Order-update subscriptions aren't scoped to a symbol, so they bypass
+    // the market registry check entirely.
+    if !matches!(event, Event::ORDERUPDATE)
+        && matches!(method, Method::SUBSCRIBE)
+        && !market_registry.read().await.is_registered(&symbol)
+    {
+        eprintln!(
+            "[ws] rejecting subscription from {} to unknown symbol: {}",
+            user_addr, symbol
+        );
+        return;
+    }
+
+    match event {
+        Event::TRADE => match method {
             Method::SUBSCRIBE => {
-                user_manager
-                    .write()
-                    .await
-                    .subscribe_trade(&user_addr.to_string(), &msg.symbol);
+                user_manager.write().await.subscribe_trade(user_addr, &symbol);
             }
             Method::UNSUBSCRIBE => {
-                user_manager
-                    .write()
-                    .await
-                    .unsubscribe_trade(&user_addr.to_string(), &msg.symbol);
+                user_manager.write().await.unsubscribe_trade(user_addr, &symbol);
             }
         },
-        Event::DEPTH => match msg.method {
+        Event::DEPTH => match method {
             Method::SUBSCRIBE => {
-                user_manager
-                    .write()
-                    .await
-                    .subscribe_depth(&user_addr, &msg.symbol);
+                user_manager.write().await.subscribe_depth(user_addr, &symbol);
             }
             Method::UNSUBSCRIBE => {
-                user_manager
-                    .write()
-                    .await
-                    .unsubscribe_depth(&user_addr, &msg.symbol);
+                user_manager.write().await.unsubscribe_depth(user_addr, &symbol);
+            }
+        },
+        Event::TICKER => match method {
+            Method::SUBSCRIBE => {
+                user_manager.write().await.subscribe_ticker(user_addr, &symbol);
+            }
+            Method::UNSUBSCRIBE => {
+                user_manager.write().await.unsubscribe_ticker(user_addr, &symbol);
             }
         },
-        Event::TICKER => match msg.method {
+        Event::BOOKTICKER => match method {
             Method::SUBSCRIBE => {
                 user_manager
                     .write()
                     .await
-                    .subscribe_ticker(&user_addr, &msg.symbol);
+                    .subscribe_book_ticker(user_addr, &symbol);
             }
             Method::UNSUBSCRIBE => {
                 user_manager
                     .write()
                     .await
-                    .unsubscribe_ticker(&user_addr, &msg.symbol);
+                    .unsubscribe_book_ticker(user_addr, &symbol);
             }
         },
-        Event::ORDERUPDATE => match msg.method {
+        Event::KLINE => {
+            let Some(interval) = interval else {
+                eprintln!(
+                    "[ws] rejecting kline subscription from {} with no interval",
+                    user_addr
+                );
+                return;
+            };
+            match method {
+                Method::SUBSCRIBE => {
+                    user_manager
+                        .write()
+                        .await
+                        .subscribe_kline(user_addr, &symbol, &interval);
+                }
+                Method::UNSUBSCRIBE => {
+                    user_manager
+                        .write()
+                        .await
+                        .unsubscribe_kline(user_addr, &symbol, &interval);
+                }
+            }
+        }
+        Event::ORDERUPDATE => match method {
             Method::SUBSCRIBE => {
-                user_manager
-                    .write()
-                    .await
-                    .associate_user(user_addr, msg.user_id.unwrap());
+                let Some(user_id) = user_id else {
+                    eprintln!(
+                        "[ws] rejecting orderUpdate subscription from {} with no user_id",
+                        user_addr
+                    );
+                    return;
+                };
+                user_manager.write().await.associate_user(user_addr, user_id);
             }
             Method::UNSUBSCRIBE => {
                 user_manager.write().await.disassociate_user(user_addr);
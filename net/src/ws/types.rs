@@ -1,17 +1,48 @@
-use enum_stringify::EnumStringify;
 use serde::Deserialize;
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
+use std::collections::HashSet;
 
 #[derive(Debug, Deserialize)]
 pub struct WsClientMessage {
     pub user_id: Option<u64>,
     pub method: Method,
-    pub event: Event,
-    pub symbol: String,
+    /// `None` when this message uses the combined/multistream `params`
+    /// form instead; exactly one of `event` or `params` should be set.
+    pub event: Option<Event>,
+    pub symbol: Option<String>,
+    /// The candle width for a `KLINE` subscription, e.g. `"1m"`/`"5m"`/`"1h"`/
+    /// `"1d"`. Ignored for every other event, where it may be left out: a
+    /// missing `Option` field deserializes as `None`, as with `user_id`.
+    pub interval: Option<String>,
+    /// Binance-style combined/multistream subscribe: each entry is
+    /// `SYMBOL@EVENT`, e.g. `"BTC_USDC@trade"`, or for klines
+    /// `SYMBOL@kline_INTERVAL`, e.g. `"BTC_USDC@kline_1m"`. When present,
+    /// `event`/`symbol`/`interval` are ignored and every token is resolved
+    /// and subscribed independently via [`parse_stream_token`].
+    pub params: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Parses one combined-stream token into `(symbol, event, interval)`.
+/// Returns `None` for a token in an unrecognized shape (no `@`, or an
+/// event name this gateway doesn't understand) rather than erroring, so one
+/// bad token in `params` doesn't take down the rest of the batch.
+pub fn parse_stream_token(token: &str) -> Option<(String, Event, Option<String>)> {
+    let (symbol, event_part) = token.split_once('@')?;
+
+    if let Some(interval) = event_part.strip_prefix("kline_") {
+        return Some((symbol.to_string(), Event::KLINE, Some(interval.to_string())));
+    }
+
+    let event = match event_part {
+        "trade" => Event::TRADE,
+        "depth" => Event::DEPTH,
+        "ticker" => Event::TICKER,
+        "bookTicker" => Event::BOOKTICKER,
+        _ => return None,
+    };
+    Some((symbol.to_string(), event, None))
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
 pub enum Method {
     SUBSCRIBE,
     UNSUBSCRIBE,
@@ -22,23 +53,36 @@ pub enum Event {
     TRADE,
     DEPTH,
     TICKER,
+    BOOKTICKER,
+    KLINE,
     ORDERUPDATE,
 }
 
-#[derive(Deserialize, PartialEq, Eq, Hash, EnumIter, EnumStringify, Clone)]
-pub enum RegisteredSymbols {
-    SOL_USDC,
-    BTC_USDc,
-    ETH_USDC,
+/// Runtime registry of tradeable symbols. Replaces the fixed three-variant
+/// `RegisteredSymbols` enum so a new market can be added without recompiling
+/// the gateway, mirroring how the engine instantiates markets at runtime.
+#[derive(Debug, Default)]
+pub struct MarketRegistry {
+    symbols: HashSet<String>,
 }
 
-impl RegisteredSymbols {
-    pub fn from_str(asset: &str) -> Option<Self> {
-        for symbol in RegisteredSymbols::iter() {
-            if symbol.to_string() == asset {
-                return Some(symbol);
-            }
+impl MarketRegistry {
+    pub fn new() -> Self {
+        Self {
+            symbols: HashSet::new(),
         }
-        None
+    }
+
+    /// Registers a new tradeable symbol.
+    pub fn instantiate_market(&mut self, symbol: &str) {
+        self.symbols.insert(symbol.to_string());
+    }
+
+    pub fn from_str(&self, symbol: &str) -> Option<String> {
+        self.symbols.get(symbol).cloned()
+    }
+
+    pub fn is_registered(&self, symbol: &str) -> bool {
+        self.symbols.contains(symbol)
     }
 }
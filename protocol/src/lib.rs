@@ -0,0 +1,11 @@
+pub mod types;
+
+#[cfg(test)]
+mod tests;
+
+pub use types::{
+    BookUpdate, BulkCancelSummary, CancelOrder, CancelReason, Event, Fill, FillRole, Order,
+    OrderAck, OrderCancelled, OrderCommand, OrderId, OrderReject, OrderStatus, OrderTriggered,
+    OrderType, Price, PriceLevel, Quantity, RejectReason, SelfTradeBehavior, Side, TimeInForce,
+    Trade, UserId,
+};
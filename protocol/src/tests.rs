@@ -54,6 +54,9 @@ mod tests {
             order_type: OrderType::Limit,
             quantity: 50,
             price: Some(50000),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            max_ts: None,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&order).unwrap();
@@ -92,7 +95,7 @@ mod tests {
     fn test_cancel_order_new() {
         let cancel = CancelOrder::new(1, 100, "SOL_USDC".to_string());
 
-        assert_eq!(cancel.order_id, 1);
+        assert_eq!(cancel.order_id, Some(1));
         assert_eq!(cancel.user_id, 100);
         assert_eq!(cancel.symbol, "SOL_USDC");
     }
@@ -100,9 +103,10 @@ mod tests {
     #[test]
     fn test_cancel_order_serialization() {
         let cancel = CancelOrder {
-            order_id: 1,
+            order_id: Some(1),
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&cancel).unwrap();
@@ -125,6 +129,9 @@ mod tests {
             order_type: OrderType::Limit,
             quantity: 50,
             price: Some(50000),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            max_ts: None,
+            client_order_id: None,
         };
 
         let command = OrderCommand::PlaceOrder(order);
@@ -144,9 +151,10 @@ mod tests {
     #[test]
     fn test_order_command_cancel_order_serialization() {
         let cancel = CancelOrder {
-            order_id: 1,
+            order_id: Some(1),
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            client_order_id: None,
         };
 
         let command = OrderCommand::CancelOrder(cancel);
@@ -188,6 +196,8 @@ mod tests {
             symbol: "SOL_USDC".to_string(),
             quantity: 50,
             price: 50000,
+            maker_fee: 0,
+            taker_fee: 0,
             timestamp: 1234567890,
         };
 
@@ -209,13 +219,18 @@ mod tests {
     #[test]
     fn test_event_fill_serialization() {
         let fill = Fill {
+            trade_id: 1,
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
             side: Side::Buy,
+            role: FillRole::Taker,
             filled_quantity: 25,
             filled_price: 50000,
             remaining_quantity: 25,
+            cumulative_quantity: 25,
+            avg_fill_price: 50000,
+            status: OrderStatus::PartiallyFilled,
         };
 
         let event = Event::Fill(fill);
@@ -239,6 +254,7 @@ mod tests {
             order_id: 1,
             user_id: 100,
             symbol: "SOL_USDC".to_string(),
+            order_type: OrderType::Limit,
         };
 
         let event = Event::OrderAck(ack);
@@ -480,6 +496,9 @@ mod tests {
             order_type: OrderType::Market,
             quantity: 999,
             price: None,
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            max_ts: None,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&original).unwrap();
@@ -505,6 +524,8 @@ mod tests {
             symbol: "ETH/USD".to_string(),
             quantity: 500,
             price: 60000,
+            maker_fee: -6,
+            taker_fee: 30,
             timestamp: 1234567890123,
         };
 
@@ -516,9 +537,37 @@ mod tests {
         assert_eq!(original.taker_order_id, deserialized.taker_order_id);
         assert_eq!(original.quantity, deserialized.quantity);
         assert_eq!(original.price, deserialized.price);
+        assert_eq!(original.maker_fee, deserialized.maker_fee);
+        assert_eq!(original.taker_fee, deserialized.taker_fee);
         assert_eq!(original.timestamp, deserialized.timestamp);
     }
 
+    #[test]
+    fn test_trade_round_trip_with_negative_maker_rebate() {
+        // A maker rebate is a negative `maker_fee`; it must still round-trip
+        // through JSON without being clamped to an unsigned type.
+        let original = Trade {
+            trade_id: 1000,
+            maker_order_id: 101,
+            maker_user_id: 201,
+            taker_order_id: 301,
+            taker_user_id: 401,
+            symbol: "ETH/USD".to_string(),
+            quantity: 500,
+            price: 60000,
+            maker_fee: -15000,
+            taker_fee: 18000,
+            timestamp: 1234567890124,
+        };
+
+        let json = serde_json::to_string(&original).unwrap();
+        let deserialized: Trade = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(original.maker_fee, deserialized.maker_fee);
+        assert_eq!(original.taker_fee, deserialized.taker_fee);
+        assert!(deserialized.maker_fee < 0);
+    }
+
     #[test]
     fn test_complete_book_update_round_trip() {
         let original = BookUpdate {
@@ -572,6 +621,9 @@ mod tests {
             order_type: OrderType::Limit,
             quantity: 0,
             price: Some(50000),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            max_ts: None,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&order).unwrap();
@@ -589,6 +641,9 @@ mod tests {
             order_type: OrderType::Limit,
             quantity: u64::MAX,
             price: Some(u64::MAX),
+            self_trade_behavior: SelfTradeBehavior::DecrementTake,
+            max_ts: None,
+            client_order_id: None,
         };
 
         let json = serde_json::to_string(&order).unwrap();
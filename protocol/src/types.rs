@@ -0,0 +1,357 @@
+use enum_stringify::EnumStringify;
+use serde::{Deserialize, Serialize};
+
+pub type OrderId = u64;
+pub type UserId = u64;
+pub type Price = u64;
+pub type Quantity = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderType {
+    Limit,
+    Market,
+    /// Reject instead of immediately crossing the book and taking liquidity.
+    PostOnly,
+    /// Match what's available now, then cancel the remainder.
+    ImmediateOrCancel,
+    /// Fully fill immediately or reject the whole order.
+    FillOrKill,
+    /// Dormant until the market trades at or through `trigger`, then arms as
+    /// a `Market` order.
+    Stop { trigger: Price },
+    /// Dormant until the market trades at or through `trigger`, then arms as
+    /// a `Limit` order at `limit`.
+    StopLimit { trigger: Price, limit: Price },
+    /// Arms as a `Market` order once price reverses `offset` away from the
+    /// best price seen since the order was placed.
+    TrailingStop { offset: Price },
+    /// Like `TrailingStop`, but the trailing distance is `pct_bps` basis
+    /// points (1/100 of a percent) of the running extreme instead of a fixed
+    /// amount.
+    TrailingStopPct { pct_bps: u16 },
+}
+
+/// How to resolve an incoming order matching against the same user's resting
+/// order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelfTradeBehavior {
+    /// Shrink the taker by the conflicting maker's quantity without
+    /// generating a trade; the maker rests untouched. Named `DecrementAndCancel`
+    /// before it was clarified that nothing here is actually cancelled; the
+    /// `alias` keeps deserializing orders serialized under the old name.
+    #[serde(alias = "DecrementAndCancel")]
+    DecrementTake,
+    /// Cancel the resting maker order and let the taker continue matching.
+    CancelProvide,
+    /// Reject the taker order outright.
+    AbortTransaction,
+}
+
+impl Default for SelfTradeBehavior {
+    fn default() -> Self {
+        SelfTradeBehavior::DecrementTake
+    }
+}
+
+/// How long a resting order stays eligible to match before it's swept off
+/// the book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeInForce {
+    GoodTillCancel,
+    GoodTillTime { expires_at_ms: u64 },
+    Day,
+}
+
+impl TimeInForce {
+    /// Resolves this time-in-force to an absolute expiry timestamp (ms since
+    /// epoch), or `None` if the order should rest indefinitely. `now_ms` is
+    /// the reference point for `Day`, which expires at the next UTC midnight
+    /// as a stand-in for a real exchange session boundary.
+    pub fn resolve_expiry_ms(&self, now_ms: i64) -> Option<i64> {
+        const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+        match self {
+            TimeInForce::GoodTillCancel => None,
+            TimeInForce::GoodTillTime { expires_at_ms } => Some(*expires_at_ms as i64),
+            TimeInForce::Day => Some(((now_ms / DAY_MILLIS) + 1) * DAY_MILLIS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Order {
+    pub order_id: OrderId,
+    pub user_id: UserId,
+    pub symbol: String,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub quantity: Quantity,
+    pub price: Option<Price>,
+    /// How to resolve a match against the same user's own resting order.
+    /// Absent from older JSON, which keeps deserializing under the default.
+    #[serde(default)]
+    pub self_trade_behavior: SelfTradeBehavior,
+    /// Unix timestamp (ms) past which this order is no longer eligible to
+    /// rest or match; `None` rests indefinitely (good-till-cancel).
+    /// `ImmediateOrCancel`/`FillOrKill` semantics live on `order_type`, not
+    /// here.
+    #[serde(default)]
+    pub max_ts: Option<u64>,
+    /// Client-chosen idempotency key. Resubmitting the same id (with the
+    /// same order parameters) returns the original `OrderAck` instead of
+    /// placing a second order; `None` opts out of dedup entirely.
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+}
+
+impl Order {
+    pub fn new(
+        order_id: OrderId,
+        user_id: UserId,
+        symbol: String,
+        side: Side,
+        order_type: OrderType,
+        quantity: Quantity,
+        price: Option<Price>,
+    ) -> Self {
+        Self {
+            order_id,
+            user_id,
+            symbol,
+            side,
+            order_type,
+            quantity,
+            price,
+            self_trade_behavior: SelfTradeBehavior::default(),
+            max_ts: None,
+            client_order_id: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CancelOrder {
+    /// The server-assigned order id to cancel. `None` if `client_order_id`
+    /// should be resolved against the dedup map instead.
+    pub order_id: Option<OrderId>,
+    pub user_id: UserId,
+    pub symbol: String,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+}
+
+impl CancelOrder {
+    pub fn new(order_id: OrderId, user_id: UserId, symbol: String) -> Self {
+        Self {
+            order_id: Some(order_id),
+            user_id,
+            symbol,
+            client_order_id: None,
+        }
+    }
+
+    /// Builds a cancel that addresses the order by the client id it was
+    /// originally placed with instead of the server-assigned `order_id`.
+    pub fn by_client_order_id(user_id: UserId, symbol: String, client_order_id: String) -> Self {
+        Self {
+            order_id: None,
+            user_id,
+            symbol,
+            client_order_id: Some(client_order_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum OrderCommand {
+    PlaceOrder(Order),
+    CancelOrder(CancelOrder),
+    GetDepth,
+    /// Sweeps every resting order whose `max_ts` is before this unix
+    /// timestamp (ms) off the book.
+    ExpireOrders(u64),
+    /// Cancels every resting order owned by `user_id`, optionally restricted
+    /// to one `symbol`, in one shot.
+    CancelAllForUser {
+        user_id: UserId,
+        symbol: Option<String>,
+    },
+    /// Cancels a specific set of `order_ids` owned by `user_id`; ids that
+    /// aren't resting (already filled, cancelled, or owned by someone else)
+    /// are silently skipped.
+    CancelOrderIds {
+        user_id: UserId,
+        order_ids: Vec<OrderId>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumStringify)]
+pub enum RejectReason {
+    InvalidPrice,
+    InvalidOrder,
+    InvalidQuantity,
+    InsufficientBalance,
+    SymbolNotFound,
+    MarketClosed,
+    InternalError,
+    /// A PostOnly order would have immediately crossed and taken liquidity.
+    WouldCross,
+    /// The order would have matched against the same user's resting order.
+    SelfTradeNotAllowed,
+    /// A FillOrKill order could not be filled in full immediately.
+    UnfillableFillOrKill,
+    /// The order's time-in-force had already elapsed at submission time.
+    Expired,
+    /// The same `client_order_id` was reused with different order parameters.
+    DuplicateClientOrderId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumStringify)]
+pub enum CancelReason {
+    UserRequested,
+    SystemCancelled,
+    Expired,
+    Liquidation,
+    /// Cancelled as the resting side of a `CancelProvide` self-trade.
+    SelfTrade,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderStatus {
+    Pending,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Rejected,
+    Expired,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceLevel {
+    pub price: Price,
+    pub quantity: Quantity,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    pub trade_id: u64,
+    pub maker_order_id: OrderId,
+    pub maker_user_id: UserId,
+    pub taker_order_id: OrderId,
+    pub taker_user_id: UserId,
+    pub symbol: String,
+    pub quantity: Quantity,
+    pub price: Price,
+    /// Fee charged to the maker, in the same integer quote-currency units
+    /// as `price`. Negative when the symbol's fee schedule pays the maker a
+    /// rebate for providing liquidity.
+    pub maker_fee: i64,
+    /// Fee charged to the taker, in the same integer quote-currency units
+    /// as `price`.
+    pub taker_fee: i64,
+    pub timestamp: i64,
+}
+
+/// Which side of a trade a `Fill` reports: the order that was already
+/// resting on the book (`Maker`) or the order that just arrived and crossed
+/// it (`Taker`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FillRole {
+    Maker,
+    Taker,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fill {
+    pub trade_id: u64,
+    pub order_id: OrderId,
+    pub user_id: UserId,
+    pub symbol: String,
+    pub side: Side,
+    pub role: FillRole,
+    pub filled_quantity: Quantity,
+    pub filled_price: Price,
+    pub remaining_quantity: Quantity,
+    /// Total quantity filled across this order's lifetime so far, including
+    /// this trade.
+    pub cumulative_quantity: Quantity,
+    /// Volume-weighted average fill price across this order's lifetime so
+    /// far, including this trade.
+    pub avg_fill_price: Price,
+    /// The order's resulting lifecycle state after this trade.
+    pub status: OrderStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderAck {
+    pub order_id: OrderId,
+    pub user_id: UserId,
+    pub symbol: String,
+    pub order_type: OrderType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderReject {
+    pub order_id: OrderId,
+    pub user_id: UserId,
+    pub reason: RejectReason,
+    pub message: String,
+    pub symbol: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderCancelled {
+    pub order_id: OrderId,
+    pub user_id: UserId,
+    pub symbol: String,
+    pub reason: CancelReason,
+}
+
+/// Emitted the moment a conditional (`Stop`/`StopLimit`/`TrailingStop`)
+/// order's trigger condition is met and it arms into a live `Market`/`Limit`
+/// order, so a subscriber can distinguish a still-dormant conditional order
+/// from one now resting or matching on the book.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderTriggered {
+    pub order_id: OrderId,
+    pub user_id: UserId,
+    pub symbol: String,
+    pub trigger_price: Price,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookUpdate {
+    pub symbol: String,
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+    pub last_price: Option<Price>,
+}
+
+/// Reported once per `CancelAllForUser`/`CancelOrderIds` command, alongside
+/// one `OrderCancelled` per order actually removed, so a market-maker client
+/// doesn't have to count cancellation events itself to know its quote refresh
+/// landed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkCancelSummary {
+    pub user_id: UserId,
+    pub symbol: Option<String>,
+    pub cancelled_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    Trade(Trade),
+    Fill(Fill),
+    OrderAck(OrderAck),
+    OrderReject(OrderReject),
+    OrderCancelled(OrderCancelled),
+    BookUpdate(BookUpdate),
+    BulkCancelSummary(BulkCancelSummary),
+    OrderTriggered(OrderTriggered),
+}